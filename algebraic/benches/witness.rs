@@ -0,0 +1,38 @@
+#[macro_use]
+extern crate criterion;
+
+use algebraic::bellman_ce::pairing::bn256::{Bn256, Fr};
+use algebraic::bellman_ce::PrimeField;
+use algebraic::witness::WitnessCalculator;
+use criterion::Criterion;
+use num_bigint::BigInt;
+
+fn bench_calculate_witness_fr(c: &mut Criterion) {
+    let wasm = concat!(env!("CARGO_MANIFEST_DIR"), "/test-vectors/mycircuit.wasm");
+    let mut wtns = WitnessCalculator::from_file(wasm).unwrap();
+    let inputs = vec![
+        ("a".to_string(), vec![BigInt::from(3u32)]),
+        ("b".to_string(), vec![BigInt::from(11u32)]),
+    ];
+
+    let mut group = c.benchmark_group("calculate_witness_fr");
+    group.bench_function("from_str", |b| {
+        b.iter(|| {
+            wtns.calculate_witness(inputs.clone(), true)
+                .unwrap()
+                .iter()
+                .map(|w| Fr::from_str(&w.to_string()).unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("parallel", |b| {
+        b.iter(|| {
+            wtns.calculate_witness_fr_parallel::<Bn256, _>(inputs.clone(), true)
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_calculate_witness_fr);
+criterion_main!(benches);
@@ -11,7 +11,9 @@ use crate::bellman_ce::{
     SynthesisError, Variable,
 };
 
+use crate::errors::EigenError;
 use crate::utils::repr_to_big;
+use crate::Field;
 
 #[derive(Serialize, Deserialize)]
 pub struct CircuitJson {
@@ -20,6 +22,8 @@ pub struct CircuitJson {
     pub num_inputs: usize,
     #[serde(rename = "nOutputs")]
     pub num_outputs: usize,
+    #[serde(rename = "nPrvInputs", default)]
+    pub num_prv_inputs: usize,
     #[serde(rename = "nVars")]
     pub num_variables: usize,
 }
@@ -51,11 +55,229 @@ pub struct R1CS<E: ScalarEngine> {
     pub num_aux: usize,
     pub num_variables: usize,
     pub num_outputs: usize,
+    pub num_prv_inputs: usize,
     pub constraints: Vec<Constraint<E>>,
     pub custom_gates: Vec<CustomGates<E>>,
     pub custom_gates_uses: Vec<CustomGatesUses>,
 }
 
+/// Number of public and private input signals of a circuit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InputArity {
+    pub public: usize,
+    pub private: usize,
+}
+
+/// Size metrics of a circuit, see `R1CS::stats`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct R1CSStats {
+    pub constraints: usize,
+    pub public_inputs: usize,
+    pub public_outputs: usize,
+    pub private_inputs: usize,
+    /// all wires, the constant one included
+    pub wires: usize,
+    pub custom_gates: usize,
+    pub custom_gate_uses: usize,
+}
+
+impl std::fmt::Display for R1CSStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "constraints: {}", self.constraints)?;
+        writeln!(f, "public inputs: {}", self.public_inputs)?;
+        writeln!(f, "public outputs: {}", self.public_outputs)?;
+        writeln!(f, "private inputs: {}", self.private_inputs)?;
+        writeln!(f, "wires: {}", self.wires)?;
+        writeln!(f, "custom gates: {}", self.custom_gates)?;
+        write!(f, "custom gate uses: {}", self.custom_gate_uses)
+    }
+}
+
+impl<E: ScalarEngine> R1CS<E> {
+    pub fn stats(&self) -> Result<R1CSStats, EigenError> {
+        let arity = self.input_arity()?;
+        Ok(R1CSStats {
+            constraints: self.constraints.len(),
+            public_inputs: arity.public,
+            public_outputs: self.num_outputs,
+            private_inputs: arity.private,
+            wires: self.num_variables,
+            custom_gates: self.custom_gates.len(),
+            custom_gate_uses: self.custom_gates_uses.len(),
+        })
+    }
+
+    /// input arity from the r1cs header, public outputs and the constant wire are excluded,
+    /// failing if the header counts more outputs than public wires
+    pub fn input_arity(&self) -> Result<InputArity, EigenError> {
+        let public = self
+            .num_inputs
+            .checked_sub(self.num_outputs)
+            .and_then(|n| n.checked_sub(1))
+            .ok_or_else(|| {
+                EigenError::MalformedR1CS(format!(
+                    "{} public wires can't hold {} outputs and the constant one",
+                    self.num_inputs, self.num_outputs
+                ))
+            })?;
+        Ok(InputArity {
+            public,
+            private: self.num_prv_inputs,
+        })
+    }
+
+    /// Put the constraints into a canonical form: the terms of every linear combination are
+    /// sorted by wire index, with repeated wires merged and zero terms dropped. With
+    /// `sort_constraints` the constraints themselves are also sorted, so circuits emitting
+    /// the same constraints in a different order canonicalize equal.
+    pub fn canonicalize(&mut self, sort_constraints: bool) {
+        fn canonical_lc<E: ScalarEngine>(lc: &mut Vec<(usize, E::Fr)>) {
+            lc.sort_by_key(|(i, _)| *i);
+            let mut merged: Vec<(usize, E::Fr)> = Vec::with_capacity(lc.len());
+            for (i, coeff) in lc.drain(..) {
+                match merged.last_mut() {
+                    Some((j, acc)) if *j == i => acc.add_assign(&coeff),
+                    _ => merged.push((i, coeff)),
+                }
+            }
+            merged.retain(|(_, coeff)| !coeff.is_zero());
+            *lc = merged;
+        }
+
+        fn lc_key<E: ScalarEngine>(
+            lc: &[(usize, E::Fr)],
+        ) -> Vec<(usize, <E::Fr as PrimeField>::Repr)> {
+            lc.iter()
+                .map(|(i, coeff)| (*i, coeff.into_repr()))
+                .collect()
+        }
+
+        for (a, b, c) in self.constraints.iter_mut() {
+            canonical_lc::<E>(a);
+            canonical_lc::<E>(b);
+            canonical_lc::<E>(c);
+        }
+        if sort_constraints {
+            self.constraints
+                .sort_by_cached_key(|(a, b, c)| (lc_key::<E>(a), lc_key::<E>(b), lc_key::<E>(c)));
+        }
+    }
+
+    /// The constraints multiplying two signals, neither `A` nor `B` being a constant,
+    /// circom's nonlinear constraints which dominate the proving cost.
+    pub fn nonlinear_constraints(&self) -> impl Iterator<Item = &Constraint<E>> {
+        self.constraints.iter().filter(|c| is_nonlinear::<E>(c))
+    }
+
+    /// The constraints where `A` or `B` is a constant, the complement of
+    /// `nonlinear_constraints`.
+    pub fn linear_constraints(&self) -> impl Iterator<Item = &Constraint<E>> {
+        self.constraints.iter().filter(|c| !is_nonlinear::<E>(c))
+    }
+
+    /// Index of the first constraint `witness` doesn't satisfy, `None` if it satisfies
+    /// them all. `witness` is indexed by wire, without a wire mapping.
+    pub fn first_unsatisfied_constraint(&self, witness: &[E::Fr]) -> Option<usize> {
+        let eval = |lc: &[(usize, E::Fr)]| {
+            let mut acc = E::Fr::zero();
+            for (i, coeff) in lc.iter() {
+                let mut term = witness.get(*i).copied().unwrap_or_else(E::Fr::zero);
+                term.mul_assign(coeff);
+                acc.add_assign(&term);
+            }
+            acc
+        };
+        self.constraints.iter().position(|(a, b, c)| {
+            let mut ab = eval(a);
+            ab.mul_assign(&eval(b));
+            ab != eval(c)
+        })
+    }
+
+    /// Append `0 * 0 = 0` constraints until their count is a power of two, for backends
+    /// whose domain needs it. Every witness satisfies the padding, so satisfiability is
+    /// unchanged.
+    pub fn pad_to_pow2(&mut self) {
+        let target = self.constraints.len().next_power_of_two();
+        self.constraints
+            .resize_with(target, || (vec![], vec![], vec![]));
+    }
+
+    /// Index of the first constraint satisfied by the trivial witness, `1` on the constant
+    /// wire and `0` everywhere else, or `None` if every constraint rejects it.
+    ///
+    /// A heuristic for auditing: such a constraint pins nothing down unless some other one
+    /// forces a signal away from zero, so a hit is worth a look, not necessarily a bug.
+    pub fn check_trivial_witness(&self) -> Option<usize> {
+        // with every other wire at zero, a linear combination evaluates to its constant term
+        fn constant<E: ScalarEngine>(lc: &[(usize, E::Fr)]) -> E::Fr {
+            let mut acc = E::Fr::zero();
+            for (_, coeff) in lc.iter().filter(|(i, _)| *i == 0) {
+                acc.add_assign(coeff);
+            }
+            acc
+        }
+        self.constraints.iter().position(|(a, b, c)| {
+            let mut ab = constant::<E>(a);
+            ab.mul_assign(&constant::<E>(b));
+            ab == constant::<E>(c)
+        })
+    }
+
+    /// Root of a BN128 Poseidon Merkle tree with one leaf per constraint, so a single
+    /// constraint can be proven part of the circuit with the tree's group proof.
+    ///
+    /// A leaf is the constraint's `a`, `b` and `c`, each encoded as its length followed by
+    /// the wire index and the 32-bit limbs of the coefficient of every term, all leaves zero
+    /// padded to the widest one.
+    #[cfg(feature = "commitment")]
+    pub fn merkle_commitment(
+        &self,
+    ) -> anyhow::Result<starky::ElementDigest<4, starky::field_bn128::Fr>> {
+        use fields::field_gl::Fr as FGL;
+        use starky::merklehash_bn128::MerkleTreeBN128;
+        use starky::traits::MerkleTree;
+
+        if self.constraints.is_empty() {
+            anyhow::bail!("can't commit to a circuit without constraints");
+        }
+        let leaves = self
+            .constraints
+            .iter()
+            .map(|(a, b, c)| {
+                let mut leaf = vec![];
+                for lc in [a, b, c] {
+                    leaf.push(FGL::from(lc.len() as u64));
+                    for (i, coeff) in lc.iter() {
+                        leaf.push(FGL::from(*i as u64));
+                        for limb in coeff.into_repr().as_ref() {
+                            leaf.push(FGL::from(*limb & 0xffffffff));
+                            leaf.push(FGL::from(*limb >> 32));
+                        }
+                    }
+                }
+                leaf
+            })
+            .collect::<Vec<_>>();
+        let width = leaves.iter().map(|l| l.len()).max().unwrap_or_default();
+        let mut buff = Vec::with_capacity(width * leaves.len());
+        for mut leaf in leaves.into_iter() {
+            leaf.resize(width, FGL::from(0u64));
+            buff.extend(leaf);
+        }
+
+        let mut tree = MerkleTreeBN128::new();
+        tree.merkelize(buff, width, self.constraints.len())?;
+        Ok(tree.root())
+    }
+}
+
+// a linear combination only over the constant wire is a constant
+fn is_nonlinear<E: ScalarEngine>((a, b, _): &Constraint<E>) -> bool {
+    let is_constant = |lc: &[(usize, E::Fr)]| lc.iter().all(|(i, _)| *i == 0);
+    !is_constant(a) && !is_constant(b)
+}
+
 #[derive(Clone, Debug)]
 pub struct CircomCircuit<E: ScalarEngine> {
     pub r1cs: R1CS<E>,
@@ -66,26 +288,60 @@ pub struct CircomCircuit<E: ScalarEngine> {
 }
 
 impl<E: ScalarEngine> CircomCircuit<E> {
-    pub fn get_public_inputs(&self) -> Option<Vec<E::Fr>> {
-        match &self.witness {
-            None => None,
-            Some(w) => match &self.wire_mapping {
-                None => Some(w[1..self.r1cs.num_inputs].to_vec()),
-                Some(m) => Some(
-                    m[1..self.r1cs.num_inputs]
-                        .iter()
-                        .map(|i| w[*i])
-                        .collect_vec(),
-                ),
-            },
+    /// Build the circuit of `r1cs` with the witness of a snarkjs `.wtns` file, which must be
+    /// over `E`'s scalar field and hold one value per r1cs variable.
+    pub fn with_witness_file(r1cs: R1CS<E>, wtns_path: &str) -> anyhow::Result<Self> {
+        let file = std::fs::File::open(wtns_path)
+            .map_err(|e| anyhow::anyhow!("Open {}, {:?}", wtns_path, e))?;
+        let witness =
+            crate::reader::load_witness_from_bin_reader::<E, _>(std::io::BufReader::new(file))?;
+        if witness.len() != r1cs.num_variables {
+            anyhow::bail!(
+                "the witness has {} elements, the r1cs {} variables",
+                witness.len(),
+                r1cs.num_variables
+            );
+        }
+        Ok(CircomCircuit {
+            r1cs,
+            witness: Some(witness),
+            wire_mapping: None,
+            aux_offset: 0,
+        })
+    }
+
+    /// The public inputs, without the leading constant one, read from the witness.
+    pub fn get_public_inputs(&self) -> Result<Vec<E::Fr>, EigenError> {
+        let w = self.witness.as_ref().ok_or(EigenError::MissingWitness)?;
+        let num_inputs = self.r1cs.num_inputs;
+        let too_short = |got| EigenError::WitnessTooShort {
+            expected: num_inputs,
+            got,
+        };
+        match &self.wire_mapping {
+            None => w
+                .get(1..num_inputs)
+                .map(|inputs| inputs.to_vec())
+                .ok_or_else(|| too_short(w.len())),
+            Some(m) => m
+                .get(1..num_inputs)
+                .ok_or_else(|| too_short(m.len()))?
+                .iter()
+                .map(|i| w.get(*i).copied().ok_or_else(|| too_short(w.len())))
+                .collect(),
         }
     }
 
+    /// The custom templates the r1cs declares, see `R1CS::custom_gates_uses` for where
+    /// they are applied.
+    pub fn custom_gates(&self) -> &[CustomGates<E>] {
+        &self.r1cs.custom_gates
+    }
+
     pub fn get_public_inputs_json(&self) -> String {
-        let inputs = self.get_public_inputs();
-        let inputs = match inputs {
-            None => return String::from("[]"),
-            Some(inp) => inp.iter().map(|x| repr_to_big(x.into_repr())).collect_vec(),
+        let inputs = match self.get_public_inputs() {
+            Err(_) => return String::from("[]"),
+            Ok(inp) => inp.iter().map(|x| repr_to_big(x.into_repr())).collect_vec(),
         };
         serde_json::to_string_pretty(&inputs).unwrap()
     }
@@ -97,6 +353,20 @@ impl<E: ScalarEngine> CircomCircuit<E> {
 impl<E: Engine> Circuit<E> for CircomCircuit<E> {
     //noinspection RsBorrowChecker
     fn synthesize<CS: ConstraintSystem<E>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        // the gates' semantics aren't in the constraints, proving without them would prove
+        // a different statement
+        if !self.r1cs.custom_gates.is_empty() {
+            let names = self
+                .r1cs
+                .custom_gates
+                .iter()
+                .map(|g| g.template_name.as_str())
+                .join(", ");
+            return Err(SynthesisError::IoError(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("custom gates are not supported by R1CS backends: {}", names),
+            )));
+        }
         let witness = &self.witness;
         let wire_mapping = &self.wire_mapping;
         for i in 1..self.r1cs.num_inputs {
@@ -160,3 +430,231 @@ impl<E: Engine> Circuit<E> for CircomCircuit<E> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bellman_ce::pairing::bn256::Bn256;
+    use crate::reader::load_r1cs;
+
+    const CIRCUIT_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../test/multiplier.r1cs");
+
+    fn constraints_to_bytes(r1cs: &R1CS<Bn256>) -> Vec<u8> {
+        use crate::PrimeFieldRepr;
+        let mut out = vec![];
+        for (a, b, c) in r1cs.constraints.iter() {
+            for lc in [a, b, c] {
+                out.extend((lc.len() as u32).to_le_bytes());
+                for (i, coeff) in lc.iter() {
+                    out.extend((*i as u32).to_le_bytes());
+                    coeff.into_repr().write_le(&mut out).unwrap();
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_get_public_inputs() {
+        use crate::bellman_ce::pairing::bn256::Fr;
+        let mut circuit = CircomCircuit {
+            r1cs: load_r1cs::<Bn256>(CIRCUIT_FILE),
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        assert!(matches!(
+            circuit.get_public_inputs(),
+            Err(EigenError::MissingWitness)
+        ));
+
+        circuit.witness = Some(vec![Fr::one()]);
+        assert!(matches!(
+            circuit.get_public_inputs(),
+            Err(EigenError::WitnessTooShort {
+                expected: 2,
+                got: 1
+            })
+        ));
+
+        let witness = ["1", "33", "3", "11"].map(|v| Fr::from_str(v).unwrap());
+        circuit.witness = Some(witness.to_vec());
+        assert_eq!(circuit.get_public_inputs().unwrap(), vec![witness[1]]);
+    }
+
+    #[test]
+    fn test_custom_gates_section() {
+        use crate::reader::load_r1cs_from_bin;
+        let mut data = std::fs::read(CIRCUIT_FILE).unwrap();
+        let num_sections = u32::from_le_bytes(data[8..12].try_into().unwrap());
+        data[8..12].copy_from_slice(&(num_sections + 2).to_le_bytes());
+        // one `Poseidon` template without parameters
+        let mut list = 1u32.to_le_bytes().to_vec();
+        list.extend(b"Poseidon\0");
+        list.extend(0u32.to_le_bytes());
+        // applied once to the signals 1 and 2
+        let uses = [1u32, 0, 2, 1, 0, 2, 0]
+            .iter()
+            .flat_map(|w| w.to_le_bytes())
+            .collect::<Vec<_>>();
+        for (ty, section) in [(4u32, list), (5, uses)] {
+            data.extend(ty.to_le_bytes());
+            data.extend((section.len() as u64).to_le_bytes());
+            data.extend(section);
+        }
+
+        let (r1cs, _) = load_r1cs_from_bin::<_, Bn256>(std::io::Cursor::new(data));
+        let circuit = CircomCircuit {
+            r1cs,
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        assert_eq!(circuit.custom_gates().len(), 1);
+        assert_eq!(circuit.custom_gates()[0].template_name, "Poseidon");
+        assert_eq!(circuit.r1cs.custom_gates_uses.len(), 1);
+        assert_eq!(circuit.r1cs.custom_gates_uses[0].signals, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = load_r1cs::<Bn256>(CIRCUIT_FILE).stats().unwrap();
+        assert_eq!(
+            stats,
+            R1CSStats {
+                constraints: 1,
+                public_inputs: 0,
+                public_outputs: 1,
+                private_inputs: 2,
+                wires: 4,
+                custom_gates: 0,
+                custom_gate_uses: 0,
+            }
+        );
+        assert!(stats.to_string().starts_with("constraints: 1\n"));
+    }
+
+    #[test]
+    fn test_input_arity() {
+        let mut r1cs = load_r1cs::<Bn256>(CIRCUIT_FILE);
+        assert_eq!(
+            r1cs.input_arity().unwrap(),
+            InputArity {
+                public: 0,
+                private: 2
+            }
+        );
+
+        r1cs.num_outputs = r1cs.num_inputs;
+        assert!(matches!(
+            r1cs.input_arity(),
+            Err(EigenError::MalformedR1CS(_))
+        ));
+        assert!(r1cs.stats().is_err());
+    }
+
+    #[test]
+    fn test_canonicalize() {
+        use crate::bellman_ce::pairing::bn256::Fr;
+        let fr = |v: &str| Fr::from_str(v).unwrap();
+        let mut r1cs = load_r1cs::<Bn256>(CIRCUIT_FILE);
+        r1cs.constraints = vec![
+            (
+                vec![(2, fr("1")), (1, fr("3"))],
+                vec![(3, fr("1"))],
+                vec![(1, fr("1")), (0, fr("0"))],
+            ),
+            (vec![(3, fr("5"))], vec![(2, fr("1"))], vec![(1, fr("2"))]),
+        ];
+
+        let mut permuted = r1cs.clone();
+        permuted.constraints.reverse();
+        permuted.constraints[1].0 = vec![(1, fr("1")), (2, fr("1")), (1, fr("2"))];
+        permuted.constraints[1].2 = vec![(1, fr("1"))];
+
+        r1cs.canonicalize(true);
+        permuted.canonicalize(true);
+        assert_eq!(constraints_to_bytes(&r1cs), constraints_to_bytes(&permuted));
+        assert_eq!(r1cs.constraints[0].0, vec![(1, fr("3")), (2, fr("1"))]);
+    }
+
+    #[test]
+    #[cfg(feature = "commitment")]
+    fn test_merkle_commitment() {
+        use crate::bellman_ce::pairing::bn256::Fr;
+        let mut r1cs = load_r1cs::<Bn256>(CIRCUIT_FILE);
+        let root = r1cs.merkle_commitment().unwrap();
+        assert_eq!(
+            root,
+            load_r1cs::<Bn256>(CIRCUIT_FILE)
+                .merkle_commitment()
+                .unwrap()
+        );
+
+        r1cs.constraints[0].2[0].1.add_assign(&Fr::one());
+        assert_ne!(root, r1cs.merkle_commitment().unwrap());
+
+        r1cs.constraints.clear();
+        assert!(r1cs.merkle_commitment().is_err());
+    }
+
+    #[test]
+    fn test_check_trivial_witness() {
+        use crate::bellman_ce::pairing::bn256::Fr;
+        let fr = |v: &str| Fr::from_str(v).unwrap();
+        let mut r1cs = load_r1cs::<Bn256>(CIRCUIT_FILE);
+        // 1 * in = 5, then out = in * in with nothing tying out down
+        r1cs.constraints = vec![
+            (vec![(0, fr("1"))], vec![(1, fr("1"))], vec![(0, fr("5"))]),
+            (vec![(1, fr("1"))], vec![(1, fr("1"))], vec![(2, fr("1"))]),
+        ];
+        assert_eq!(r1cs.check_trivial_witness(), Some(1));
+
+        r1cs.constraints.truncate(1);
+        assert_eq!(r1cs.check_trivial_witness(), None);
+    }
+
+    #[test]
+    fn test_pad_to_pow2() {
+        use crate::bellman_ce::pairing::bn256::Fr;
+        let fr = |v: &str| Fr::from_str(v).unwrap();
+        // 1, c, a, b of multiplier.input.json
+        let witness = ["1", "33", "3", "11"].map(fr);
+        let mut r1cs = load_r1cs::<Bn256>(CIRCUIT_FILE);
+        let constraint = r1cs.constraints[0].clone();
+        r1cs.constraints.push(constraint.clone());
+        r1cs.constraints.push(constraint);
+        assert_eq!(r1cs.first_unsatisfied_constraint(&witness), None);
+
+        r1cs.pad_to_pow2();
+        assert_eq!(r1cs.constraints.len(), 4);
+        assert_eq!(r1cs.first_unsatisfied_constraint(&witness), None);
+        assert_eq!(
+            r1cs.first_unsatisfied_constraint(&["1", "34", "3", "11"].map(fr)),
+            Some(0)
+        );
+        r1cs.pad_to_pow2();
+        assert_eq!(r1cs.constraints.len(), 4);
+    }
+
+    #[test]
+    fn test_nonlinear_constraints() {
+        use crate::bellman_ce::pairing::bn256::Fr;
+        let fr = |v: &str| Fr::from_str(v).unwrap();
+        let mut r1cs = load_r1cs::<Bn256>(CIRCUIT_FILE);
+        assert_eq!(r1cs.nonlinear_constraints().count(), 1);
+        assert_eq!(r1cs.linear_constraints().count(), 0);
+
+        // 1 * (a + 2) = b
+        r1cs.constraints.push((
+            vec![(0, fr("1"))],
+            vec![(2, fr("1")), (0, fr("2"))],
+            vec![(3, fr("1"))],
+        ));
+        assert_eq!(r1cs.nonlinear_constraints().count(), 1);
+        assert_eq!(
+            r1cs.linear_constraints().collect::<Vec<_>>(),
+            vec![&r1cs.constraints[1]]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Dispatch circom custom templates, `CustomGates` in the r1cs, to backend handlers by name.
+use crate::bellman_ce::ScalarEngine;
+use crate::circom_circuit::R1CS;
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashMap;
+
+/// Backend support for one custom template.
+pub trait CustomGateHandler<E: ScalarEngine> {
+    /// Check one use of the gate, `parameters` are the template's and `signals` the wires
+    /// it is applied to.
+    fn check_use(&self, _parameters: &[E::Fr], _signals: &[u64]) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub struct CustomGateRegistry<E: ScalarEngine> {
+    handlers: HashMap<String, Box<dyn CustomGateHandler<E>>>,
+}
+
+impl<E: ScalarEngine> Default for CustomGateRegistry<E> {
+    fn default() -> Self {
+        CustomGateRegistry {
+            handlers: HashMap::new(),
+        }
+    }
+}
+
+impl<E: ScalarEngine> CustomGateRegistry<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `handler` for the template `name`, returning the one it replaces if any.
+    pub fn register(
+        &mut self,
+        name: &str,
+        handler: Box<dyn CustomGateHandler<E>>,
+    ) -> Option<Box<dyn CustomGateHandler<E>>> {
+        self.handlers.insert(name.to_string(), handler)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn CustomGateHandler<E>> {
+        self.handlers.get(name).map(|h| h.as_ref())
+    }
+
+    /// Check every custom gate used by `r1cs` has a registered handler, and let the handler
+    /// check each of its uses.
+    pub fn validate(&self, r1cs: &R1CS<E>) -> Result<()> {
+        let mut missing = r1cs
+            .custom_gates
+            .iter()
+            .map(|g| g.template_name.as_str())
+            .filter(|name| !self.handlers.contains_key(*name))
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            missing.sort_unstable();
+            missing.dedup();
+            bail!("no handler for custom gates: {}", missing.join(", "));
+        }
+        for gate_use in r1cs.custom_gates_uses.iter() {
+            let gate = r1cs
+                .custom_gates
+                .get(gate_use.id as usize)
+                .ok_or_else(|| anyhow!("custom gate use refers to unknown gate {}", gate_use.id))?;
+            self.handlers[&gate.template_name]
+                .check_use(&gate.parameters, &gate_use.signals)
+                .map_err(|e| anyhow!("{}: {}", gate.template_name, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bellman_ce::pairing::bn256::Bn256;
+    use crate::circom_circuit::{CustomGates, CustomGatesUses};
+    use crate::reader::load_r1cs;
+
+    const CIRCUIT_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../test/multiplier.r1cs");
+
+    struct Poseidon;
+
+    impl CustomGateHandler<Bn256> for Poseidon {
+        fn check_use(
+            &self,
+            _parameters: &[<Bn256 as ScalarEngine>::Fr],
+            signals: &[u64],
+        ) -> Result<()> {
+            if signals.len() != 3 {
+                bail!("expect 3 signals, got {}", signals.len());
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_gate_registry() {
+        let mut r1cs = load_r1cs::<Bn256>(CIRCUIT_FILE);
+        r1cs.custom_gates.push(CustomGates {
+            template_name: "Poseidon".to_string(),
+            parameters: vec![],
+        });
+        r1cs.custom_gates_uses.push(CustomGatesUses {
+            id: 0,
+            signals: vec![1, 2, 3],
+        });
+
+        let mut registry = CustomGateRegistry::<Bn256>::new();
+        assert!(registry.validate(&r1cs).is_err());
+        assert!(registry.register("Poseidon", Box::new(Poseidon)).is_none());
+        assert!(registry.get("Poseidon").is_some());
+        registry.validate(&r1cs).unwrap();
+
+        r1cs.custom_gates_uses[0].signals.pop();
+        assert!(registry.validate(&r1cs).is_err());
+    }
+}
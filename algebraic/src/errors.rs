@@ -0,0 +1,72 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum EigenError {
+    #[error("malformed witness, '{0}'")]
+    MalformedWitness(String),
+
+    #[error("no witness set")]
+    MissingWitness,
+
+    #[error("malformed r1cs, '{0}'")]
+    MalformedR1CS(String),
+
+    /// `expected` counts the leading constant one, as the r1cs `num_inputs` does
+    #[error("witness too short, {expected} public input wires expected, got {got}")]
+    WitnessTooShort { expected: usize, got: usize },
+
+    #[error("invalid verifying key, '{0}'")]
+    InvalidVerifyingKey(String),
+
+    #[error("invalid point, '{0}'")]
+    InvalidPoint(String),
+
+    #[error("invalid scalar, '{0}'")]
+    InvalidScalar(String),
+
+    #[error("invalid proof, '{0}'")]
+    InvalidProof(String),
+
+    #[error("invalid public inputs, '{0}'")]
+    InvalidPublicInputs(String),
+
+    /// `expected` is the verifying key's IC length minus one
+    #[error("expect {expected} public inputs, got {got}")]
+    PublicInputLength { expected: usize, got: usize },
+
+    /// `expected` is 0 for a name the circuit has no input for
+    #[error("input signal {name}, expect {expected} values, got {got}")]
+    InputSignal {
+        name: String,
+        expected: usize,
+        got: usize,
+    },
+
+    /// `path` is the JSON pointer of the offending value
+    #[error("invalid input value at {path:?}, {reason}")]
+    InputValue { path: String, reason: String },
+
+    #[error("curve mismatch, found {found}, but {expected} is expected")]
+    CurveMismatch { expected: String, found: String },
+
+    #[error("load wasm, '{0}'")]
+    WasmLoad(String),
+
+    #[error("wasm memory, '{0}'")]
+    WasmMemory(String),
+
+    #[error("wasm memory access out of bounds, {len} bytes at {offset}")]
+    MemoryOutOfBounds { offset: u64, len: u64 },
+
+    #[error("unsupported, '{0}'")]
+    Unsupported(String),
+
+    #[error("Unknown error, `{0}`")]
+    Unknown(String),
+}
+
+impl From<String> for EigenError {
+    fn from(e: String) -> Self {
+        EigenError::Unknown(e)
+    }
+}
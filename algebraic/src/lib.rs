@@ -12,6 +12,8 @@ extern crate num_traits;
 extern crate rand;
 
 pub mod circom_circuit;
+pub mod custom_gates;
+pub mod errors;
 pub mod r1cs_file;
 pub mod reader;
 pub mod utils;
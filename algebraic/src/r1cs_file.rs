@@ -125,19 +125,17 @@ fn read_map<R: Read>(mut reader: R, size: u64, header: &Header) -> Result<Vec<u6
     Ok(vec)
 }
 
-// TODO: why does the `read_to_end` not work?
-fn read_to_string<R: Read>(mut reader: R) -> String {
-    let mut name_buf = vec![1u8; 1];
+// a NUL terminated string, read byte by byte so the reader stops right after the NUL
+fn read_to_string<R: Read>(mut reader: R) -> Result<String> {
     let mut buf = vec![];
     loop {
-        let name_size_res = reader.read_exact(&mut name_buf);
-        if name_buf[0] != 0 {
-            buf.push(name_buf[0]);
-        } else {
+        let byte = reader.read_u8()?;
+        if byte == 0 {
             break;
         }
+        buf.push(byte);
     }
-    String::from_utf8_lossy(&buf).to_string()
+    String::from_utf8(buf).map_err(|e| Error::new(ErrorKind::InvalidData, e))
 }
 
 fn read_custom_gates_list<R: Read, E: ScalarEngine>(
@@ -149,7 +147,7 @@ fn read_custom_gates_list<R: Read, E: ScalarEngine>(
     let mut custom_gates: Vec<CustomGates<E>> = vec![];
     for i in 0..num {
         let mut custom_gate = CustomGates::<E> {
-            template_name: read_to_string(&mut reader),
+            template_name: read_to_string(&mut reader)?,
             parameters: vec![],
         };
         let num_parameters = reader.read_u32::<LittleEndian>()?;
@@ -176,21 +174,23 @@ fn read_custom_gates_uses_list<R: Read>(
         b_r1cs32.push(reader.read_u32::<LittleEndian>()?);
     }
 
-    let n_custom_gate_uses = b_r1cs32[0];
-    let mut b_r1cs_pos = 1;
+    let mut words = b_r1cs32.into_iter();
+    // the counts are read from the file, so they may exceed what it holds
+    let mut next = || {
+        words
+            .next()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "custom gate uses section truncated"))
+    };
+    let n_custom_gate_uses = next()?;
     for i in 0..n_custom_gate_uses {
         let mut c = CustomGatesUses {
-            id: b_r1cs32[b_r1cs_pos] as u64,
+            id: next()? as u64,
             ..Default::default()
         };
-        b_r1cs_pos += 1;
-        let num_signals = b_r1cs32[b_r1cs_pos];
-        b_r1cs_pos += 1;
+        let num_signals = next()?;
         for j in 0..num_signals {
-            let LSB = b_r1cs32[b_r1cs_pos] as u64;
-            b_r1cs_pos += 1;
-            let MSB = b_r1cs32[b_r1cs_pos] as u64;
-            b_r1cs_pos += 1;
+            let LSB = next()? as u64;
+            let MSB = next()? as u64;
             c.signals.push(MSB * 0x100000000u64 + LSB);
         }
         custom_gates_uses.push(c);
@@ -198,6 +198,30 @@ fn read_custom_gates_uses_list<R: Read>(
     Ok(custom_gates_uses)
 }
 
+/// Read only the header section, for the sizes of a circuit without parsing its
+/// constraints, nor checking its prime.
+pub fn header_from_reader<R: Read + Seek>(mut reader: R) -> Result<Header> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != [0x72, 0x31, 0x63, 0x73] {
+        return Err(Error::new(ErrorKind::InvalidData, "Invalid magic number"));
+    }
+    let version = reader.read_u32::<LittleEndian>()?;
+    if version != 1 {
+        return Err(Error::new(ErrorKind::InvalidData, "Unsupported version"));
+    }
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+    for _ in 0..num_sections {
+        let section_type = reader.read_u32::<LittleEndian>()?;
+        let section_size = reader.read_u64::<LittleEndian>()?;
+        if section_type == HEADER_TYPE {
+            return read_header(&mut reader, section_size);
+        }
+        reader.seek(SeekFrom::Current(section_size as i64))?;
+    }
+    Err(Error::new(ErrorKind::InvalidData, "Missing header section"))
+}
+
 pub fn from_reader<R: Read + Seek, E: ScalarEngine>(mut reader: R) -> Result<R1CSFile<E>> {
     let mut magic = [0u8; 4];
     reader.read_exact(&mut magic)?;
@@ -241,8 +265,17 @@ pub fn from_reader<R: Read + Seek, E: ScalarEngine>(mut reader: R) -> Result<R1C
             "This parser only supports 32-bytes or 8-bytes fields",
         ));
     }
-    if header.field_size != (E::Fr::NUM_BITS + 7) / 8 {
-        return Err(Error::new(ErrorKind::InvalidData, "Different prime"));
+    let mut modulus = vec![];
+    E::Fr::char().write_le(&mut modulus)?;
+    if header.field_size as usize != modulus.len() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Field size mismatch, {} bytes in the r1cs, {} in the engine's scalar field",
+                header.field_size,
+                modulus.len()
+            ),
+        ));
     }
     if !(header.prime_size
         == hex!("010000f093f5e1439170b97948e833285d588181b64550b829a031e1724e6430")
@@ -255,6 +288,12 @@ pub fn from_reader<R: Read + Seek, E: ScalarEngine>(mut reader: R) -> Result<R1C
             "This parser only supports bn256 or GL",
         ));
     }
+    if header.prime_size != modulus {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "Prime mismatch, the r1cs is not over the engine's scalar field",
+        ));
+    }
     reader.seek(SeekFrom::Start(
         *section_offsets.get(&CONSTRAINT_TYPE).unwrap(),
     ))?;
@@ -395,6 +434,82 @@ mod tests {
         assert_eq!(file.wire_mapping[1], 3);
     }
 
+    #[test]
+    fn test_read_to_string() {
+        let mut data = b"Poseidon\0".to_vec();
+        data.extend(2u32.to_le_bytes());
+        let mut reader = Cursor::new(&data[..]);
+        assert_eq!(read_to_string(&mut reader).unwrap(), "Poseidon");
+        assert_eq!(reader.position(), 9);
+        assert_eq!(reader.read_u32::<LittleEndian>().unwrap(), 2);
+
+        // unterminated, and not UTF-8
+        let err = read_to_string(&mut &b"Poseidon"[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        let err = read_to_string(&mut &b"\xffPoseidon\0"[..]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_custom_gates_uses_truncated() {
+        let section = |words: &[u32]| {
+            words
+                .iter()
+                .flat_map(|w| w.to_le_bytes())
+                .collect::<Vec<_>>()
+        };
+        // one use of gate 0 on the signals 1 and 2
+        let data = section(&[1, 0, 2, 1, 0, 2, 0]);
+        let uses =
+            read_custom_gates_uses_list(&data[..], data.len() as u64, &Header::default()).unwrap();
+        assert_eq!(uses[0].signals, vec![1, 2]);
+
+        // the second signal is missing its high word, then no data at all
+        for data in [section(&[1, 0, 2, 1, 0, 2]), vec![]] {
+            let err = read_custom_gates_uses_list(&data[..], data.len() as u64, &Header::default())
+                .unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData);
+            assert_eq!(err.to_string(), "custom gate uses section truncated");
+        }
+    }
+
+    // an r1cs with only a header section, over a `field_size` bytes `prime`
+    fn header_only(prime: &[u8]) -> Vec<u8> {
+        let mut data = b"r1cs".to_vec();
+        for w in [1u32, 1, HEADER_TYPE] {
+            data.extend(w.to_le_bytes());
+        }
+        data.extend((32 + prime.len() as u64).to_le_bytes());
+        data.extend((prime.len() as u32).to_le_bytes());
+        data.extend(prime);
+        // wires, public outputs, public inputs, private inputs, labels and constraints
+        for w in [4u32, 1, 0, 2] {
+            data.extend(w.to_le_bytes());
+        }
+        data.extend(4u64.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_field_mismatch() {
+        let data = header_only(&hex!("01000000ffffffff"));
+        let err = from_reader::<_, Bn256>(Cursor::new(&data[..])).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(
+            err.to_string().starts_with("Field size mismatch"),
+            "{}",
+            err
+        );
+
+        // bls12-381's scalar field, also 32 bytes
+        let data = header_only(&hex!(
+            "01000000fffffffffe5bfeff02a4bd5305d8a10908d83933487d9d2953a7ed73"
+        ));
+        let err = from_reader::<_, Bn256>(Cursor::new(&data[..])).unwrap_err();
+        assert!(err.to_string().starts_with("Prime mismatch"), "{}", err);
+    }
+
     #[test]
     fn test_reader_size_fail() {
         // fn read_header<R: Read>(mut reader: R, size: u64) -> Result<Header>
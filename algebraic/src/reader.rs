@@ -1,18 +1,23 @@
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use byteorder::{LittleEndian, ReadBytesExt};
 use itertools::Itertools;
 use std::collections::BTreeMap;
 use std::fs::{File, OpenOptions};
-use std::io::{BufReader, Read, Seek};
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::str;
 
 use crate::bellman_ce::{
+    groth16::VerifyingKey,
     kate_commitment::{Crs, CrsForLagrangeForm, CrsForMonomialForm},
-    pairing::Engine,
+    pairing::{
+        bn256::{Bn256, Fq, Fq2, G1Affine, G2Affine},
+        CurveAffine, Engine,
+    },
     Field, PrimeField, PrimeFieldRepr, ScalarEngine,
 };
 
 use crate::circom_circuit::{CircuitJson, R1CS};
+use crate::errors::EigenError;
 
 /// get universal setup file by filename
 fn get_universal_setup_file_buff_reader(setup_file_name: &str) -> Result<BufReader<File>> {
@@ -85,58 +90,249 @@ pub fn load_witness_from_array<E: ScalarEngine>(buffer: Vec<u8>) -> Result<Vec<E
     load_witness_from_bin_reader::<E, _>(buffer.as_slice())
 }
 
-/// load witness from u8 array by a reader
+/// load witness from u8 array by a reader, the file's prime must be the modulus of `E::Fr`
 pub fn load_witness_from_bin_reader<E: ScalarEngine, R: Read>(mut reader: R) -> Result<Vec<E::Fr>> {
+    let stats = read_wtns_header(&mut reader)?;
+    let mut modulus = vec![];
+    E::Fr::char().write_le(&mut modulus)?;
+    if stats.field_size as usize != modulus.len() {
+        bail!("invalid field byte size".to_string());
+    }
+    if stats.prime != modulus {
+        bail!("invalid curve prime".to_string());
+    }
+    log::trace!("witness len {}", stats.witness_size);
+    read_wtns_data(&mut reader, &stats)?
+        .chunks(stats.field_size as usize)
+        .map(|mut bytes| {
+            let mut repr = E::Fr::zero().into_repr();
+            repr.read_le(&mut bytes)?;
+            Ok(E::Fr::from_repr(repr)?)
+        })
+        .collect()
+}
+
+/// Sizes declared by a `.wtns` file, see `validate_wtns_file`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WtnsStats {
+    pub version: u32,
+    /// bytes per field element
+    pub field_size: u32,
+    /// the prime, little-endian
+    pub prime: Vec<u8>,
+    pub witness_size: u32,
+}
+
+/// Read a `.wtns` up to its witness data: the magic, the version, the header section, which
+/// must be `4 + field_size + 4` bytes, and the header of the witness section, which must be
+/// `witness_size * field_size` bytes.
+pub(crate) fn read_wtns_header<R: Read>(reader: &mut R) -> Result<WtnsStats> {
     let mut wtns_header = [0u8; 4];
     reader.read_exact(&mut wtns_header)?;
-    if wtns_header != [119, 116, 110, 115] {
-        // python -c 'print([ord(c) for c in "wtns"])' => [119, 116, 110, 115]
-        bail!("Invalid file header");
+    if &wtns_header != b"wtns" {
+        bail!("invalid file header");
     }
     let version = reader.read_u32::<LittleEndian>()?;
     log::trace!("wtns version {}", version);
-    if version > 2 {
-        bail!("unsupported file version");
+    if !(1..=2).contains(&version) {
+        bail!("unsupported file version {}", version);
     }
     let num_sections = reader.read_u32::<LittleEndian>()?;
     if num_sections != 2 {
-        bail!("invalid num sections".to_string());
+        bail!("expect 2 sections, got {}", num_sections);
     }
-    // read the first section
     let sec_type = reader.read_u32::<LittleEndian>()?;
-    if sec_type != 1 {
-        bail!("invalid section type".to_string());
-    }
     let sec_size = reader.read_u64::<LittleEndian>()?;
-    if sec_size != 4 + 32 + 4 {
-        bail!("invalid section len".to_string());
+    if sec_type != 1 {
+        bail!("expect the header section first, got {}", sec_type);
     }
     let field_size = reader.read_u32::<LittleEndian>()?;
-    if field_size != 32 {
-        bail!("invalid field byte size".to_string());
+    if sec_size != 4 + field_size as u64 + 4 {
+        bail!(
+            "header section is {} bytes, expect {} for {}-byte field elements",
+            sec_size,
+            4 + field_size as u64 + 4,
+            field_size
+        );
     }
-    let mut prime = vec![0u8; field_size as usize];
-    reader.read_exact(&mut prime)?;
-    if prime != hex!("010000f093f5e1439170b97948e833285d588181b64550b829a031e1724e6430") {
-        bail!("invalid curve prime".to_string());
+    let mut prime = vec![];
+    (&mut *reader)
+        .take(field_size as u64)
+        .read_to_end(&mut prime)?;
+    if prime.len() != field_size as usize {
+        bail!("truncated header section");
     }
-    let witness_len = reader.read_u32::<LittleEndian>()?;
-    log::trace!("witness len {}", witness_len);
+    let witness_size = reader.read_u32::<LittleEndian>()?;
+
     let sec_type = reader.read_u32::<LittleEndian>()?;
+    let sec_size = reader.read_u64::<LittleEndian>()?;
     if sec_type != 2 {
-        bail!("invalid section type".to_string());
+        bail!("expect the witness section second, got {}", sec_type);
     }
-    let sec_size = reader.read_u64::<LittleEndian>()?;
-    if sec_size != (witness_len * field_size) as u64 {
-        bail!(format!("Invalid witness section size {}", sec_size));
+    let expected = witness_size as u64 * field_size as u64;
+    if sec_size != expected {
+        bail!(
+            "witness section is {} bytes, but {} elements of {} bytes need {}",
+            sec_size,
+            witness_size,
+            field_size,
+            expected
+        );
+    }
+    Ok(WtnsStats {
+        version,
+        field_size,
+        prime,
+        witness_size,
+    })
+}
+
+/// The witness data following `read_wtns_header`, as many bytes as the reader really holds
+/// rather than what the header claims, failing if that's fewer.
+pub(crate) fn read_wtns_data<R: Read>(reader: &mut R, stats: &WtnsStats) -> Result<Vec<u8>> {
+    let size = stats.witness_size as u64 * stats.field_size as u64;
+    let mut data = vec![];
+    (&mut *reader).take(size).read_to_end(&mut data)?;
+    if data.len() as u64 != size {
+        bail!(
+            "witness section declares {} bytes, only {} are there",
+            size,
+            data.len()
+        );
+    }
+    Ok(data)
+}
+
+/// Check the header of a `.wtns` file is consistent with its data: the witness section is
+/// `witness_size * field_size` bytes long and the file really holds that many bytes.
+pub fn validate_wtns_file(filename: &str) -> Result<WtnsStats> {
+    let file = File::open(filename).map_err(|e| anyhow!("Open {}, {:?}", filename, e))?;
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let stats = read_wtns_header(&mut reader).map_err(|e| anyhow!("{}: {}", filename, e))?;
+
+    // magic, version, section count, then two section headers
+    let data_start = 4 + 4 + 4 + (4 + 8) + (4 + stats.field_size as u64 + 4) + (4 + 8);
+    let sec_size = stats.witness_size as u64 * stats.field_size as u64;
+    let available = file_len.saturating_sub(data_start);
+    if available != sec_size {
+        bail!(
+            "{}: witness section declares {} bytes, the file holds {}, truncated or corrupt",
+            filename,
+            sec_size,
+            available
+        );
+    }
+    Ok(stats)
+}
+
+/// load the verifying key embedded in a snarkjs groth16 zkey over bn128, the proving key
+/// sections are skipped
+pub fn load_bn256_vk_from_zkey(filename: &str) -> Result<VerifyingKey<Bn256>> {
+    let mut reader = BufReader::new(File::open(filename)?);
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"zkey" {
+        bail!("Invalid file header");
+    }
+    let version = reader.read_u32::<LittleEndian>()?;
+    if version != 1 {
+        bail!("unsupported file version");
+    }
+    let num_sections = reader.read_u32::<LittleEndian>()?;
+    let mut sections: BTreeMap<u32, Vec<u8>> = BTreeMap::new();
+    for _ in 0..num_sections {
+        let sec_type = reader.read_u32::<LittleEndian>()?;
+        let sec_size = reader.read_u64::<LittleEndian>()?;
+        if (1..=3).contains(&sec_type) {
+            let mut buff = vec![];
+            (&mut reader).take(sec_size).read_to_end(&mut buff)?;
+            if buff.len() as u64 != sec_size {
+                bail!("zkey section {} is truncated", sec_type);
+            }
+            sections.insert(sec_type, buff);
+        } else {
+            reader.seek(SeekFrom::Current(sec_size as i64))?;
+        }
+    }
+    let section = |id: u32| {
+        sections
+            .get(&id)
+            .map(|s| s.as_slice())
+            .ok_or_else(|| anyhow::anyhow!("missing zkey section {}", id))
+    };
+
+    // points are stored as little endian Montgomery coordinates
+    fn read_fq<R: Read>(reader: &mut R) -> Result<Fq> {
+        let mut repr = Fq::zero().into_raw_repr();
+        repr.read_le(reader)?;
+        Ok(Fq::from_raw_repr(repr)?)
+    }
+    fn read_g1<R: Read>(reader: &mut R) -> Result<G1Affine> {
+        let (x, y) = (read_fq(reader)?, read_fq(reader)?);
+        if x.is_zero() && y.is_zero() {
+            return Ok(G1Affine::zero());
+        }
+        Ok(G1Affine::from_xy_checked(x, y)?)
+    }
+    fn read_g2<R: Read>(reader: &mut R) -> Result<G2Affine> {
+        let x = Fq2 {
+            c0: read_fq(reader)?,
+            c1: read_fq(reader)?,
+        };
+        let y = Fq2 {
+            c0: read_fq(reader)?,
+            c1: read_fq(reader)?,
+        };
+        if x.is_zero() && y.is_zero() {
+            return Ok(G2Affine::zero());
+        }
+        Ok(G2Affine::from_xy_checked(x, y)?)
+    }
+
+    let mut header = section(1)?;
+    if header.read_u32::<LittleEndian>()? != 1 {
+        bail!("not a groth16 zkey");
     }
-    let mut result = Vec::with_capacity(witness_len as usize);
-    for _ in 0..witness_len {
-        let mut repr = E::Fr::zero().into_repr();
-        repr.read_le(&mut reader)?;
-        result.push(E::Fr::from_repr(repr)?);
+
+    let mut header = section(2)?;
+    let mut expected_q = vec![];
+    Fq::char().write_le(&mut expected_q)?;
+    let n8q = header.read_u32::<LittleEndian>()?;
+    if n8q as usize != expected_q.len() {
+        bail!("invalid curve prime".to_string());
+    }
+    let mut q = vec![0u8; expected_q.len()];
+    header.read_exact(&mut q)?;
+    if q != expected_q {
+        bail!("invalid curve prime".to_string());
     }
-    Ok(result)
+    let n8r = header.read_u32::<LittleEndian>()?;
+    std::io::copy(&mut (&mut header).take(n8r as u64), &mut std::io::sink())?;
+    let _n_vars = header.read_u32::<LittleEndian>()?;
+    let n_public = header.read_u32::<LittleEndian>()?;
+    let _domain_size = header.read_u32::<LittleEndian>()?;
+    let alpha_g1 = read_g1(&mut header)?;
+    let beta_g1 = read_g1(&mut header)?;
+    let beta_g2 = read_g2(&mut header)?;
+    let gamma_g2 = read_g2(&mut header)?;
+    let delta_g1 = read_g1(&mut header)?;
+    let delta_g2 = read_g2(&mut header)?;
+
+    let mut ic_section = section(3)?;
+    let ic = (0..=n_public)
+        .map(|_| read_g1(&mut ic_section))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(VerifyingKey {
+        alpha_g1,
+        beta_g1,
+        beta_g2,
+        gamma_g2,
+        delta_g1,
+        delta_g2,
+        ic,
+    })
 }
 
 /// load r1cs file by filename with autodetect encoding (bin or json)
@@ -149,6 +345,27 @@ pub fn load_r1cs<E: ScalarEngine>(filename: &str) -> R1CS<E> {
     }
 }
 
+/// Load a binary r1cs file like `load_r1cs`, checking first that its prime is `E::Fr`'s
+/// modulus, so an artifact compiled for another curve is rejected with
+/// `EigenError::CurveMismatch` instead of building a meaningless circuit.
+pub fn load_r1cs_for<E: ScalarEngine>(filename: &str) -> Result<R1CS<E>> {
+    let mut reader =
+        BufReader::new(File::open(filename).map_err(|e| anyhow!("Open {}, {:?}", filename, e))?);
+    let header = crate::r1cs_file::header_from_reader(&mut reader)?;
+    let mut modulus = vec![];
+    E::Fr::char().write_le(&mut modulus)?;
+    if header.prime_size != modulus {
+        bail!(EigenError::CurveMismatch {
+            expected: num_bigint::BigUint::from_bytes_le(&modulus).to_string(),
+            found: num_bigint::BigUint::from_bytes_le(&header.prime_size).to_string(),
+        });
+    }
+    reader.seek(SeekFrom::Start(0))?;
+    let file = crate::r1cs_file::from_reader::<_, E>(reader)?;
+    let (r1cs, _wire_mapping) = r1cs_from_file(file);
+    Ok(r1cs)
+}
+
 /// load r1cs from json file by filename
 fn load_r1cs_from_json_file<E: ScalarEngine>(filename: &str) -> R1CS<E> {
     let reader = OpenOptions::new()
@@ -188,6 +405,7 @@ fn load_r1cs_from_json<E: ScalarEngine, R: Read>(reader: R) -> R1CS<E> {
         num_aux,
         num_variables: circuit_json.num_variables,
         num_outputs: circuit_json.num_outputs,
+        num_prv_inputs: circuit_json.num_prv_inputs,
         constraints,
         custom_gates: vec![],
         custom_gates_uses: vec![],
@@ -206,6 +424,10 @@ fn load_r1cs_from_bin_file<E: ScalarEngine>(filename: &str) -> (R1CS<E>, Vec<usi
 /// load r1cs from bin by a reader
 pub fn load_r1cs_from_bin<R: Read + Seek, E: ScalarEngine>(reader: R) -> (R1CS<E>, Vec<usize>) {
     let file = crate::r1cs_file::from_reader::<R, E>(reader).expect("Unable to read.");
+    r1cs_from_file(file)
+}
+
+fn r1cs_from_file<E: ScalarEngine>(file: crate::r1cs_file::R1CSFile<E>) -> (R1CS<E>, Vec<usize>) {
     let num_inputs = (1 + file.header.n_pub_in + file.header.n_pub_out) as usize;
     let num_variables = file.header.n_wires as usize;
     let num_aux = num_variables - num_inputs;
@@ -215,6 +437,7 @@ pub fn load_r1cs_from_bin<R: Read + Seek, E: ScalarEngine>(reader: R) -> (R1CS<E
             num_inputs,
             num_variables,
             num_outputs: file.header.n_pub_out as usize,
+            num_prv_inputs: file.header.n_prv_in as usize,
             constraints: file.constraints,
             custom_gates: file.custom_gates,
             custom_gates_uses: file.custom_gates_uses,
@@ -222,3 +445,199 @@ pub fn load_r1cs_from_bin<R: Read + Seek, E: ScalarEngine>(reader: R) -> (R1CS<E
         file.wire_mapping.iter().map(|e| *e as usize).collect_vec(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bellman_ce::pairing::{bn256::Fr, CurveProjective};
+    use byteorder::WriteBytesExt;
+    use std::io::Write;
+
+    fn write_fq(out: &mut Vec<u8>, v: &Fq) {
+        v.into_raw_repr().write_le(out).unwrap();
+    }
+
+    fn write_g1(out: &mut Vec<u8>, p: &G1Affine) {
+        let (x, y) = p.into_xy_unchecked();
+        write_fq(out, &x);
+        write_fq(out, &y);
+    }
+
+    fn write_g2(out: &mut Vec<u8>, p: &G2Affine) {
+        let (x, y) = p.into_xy_unchecked();
+        for v in [x.c0, x.c1, y.c0, y.c1] {
+            write_fq(out, &v);
+        }
+    }
+
+    fn write_section(out: &mut Vec<u8>, id: u32, content: &[u8]) {
+        out.write_u32::<LittleEndian>(id).unwrap();
+        out.write_u64::<LittleEndian>(content.len() as u64).unwrap();
+        out.write_all(content).unwrap();
+    }
+
+    // a groth16 zkey holding `vk` and a stand-in proving key section
+    fn zkey_for(vk: &VerifyingKey<Bn256>) -> Vec<u8> {
+        let mut header = vec![];
+        header.write_u32::<LittleEndian>(32).unwrap();
+        Fq::char().write_le(&mut header).unwrap();
+        header.write_u32::<LittleEndian>(32).unwrap();
+        Fr::char().write_le(&mut header).unwrap();
+        for v in [4u32, vk.ic.len() as u32 - 1, 4] {
+            header.write_u32::<LittleEndian>(v).unwrap();
+        }
+        write_g1(&mut header, &vk.alpha_g1);
+        write_g1(&mut header, &vk.beta_g1);
+        write_g2(&mut header, &vk.beta_g2);
+        write_g2(&mut header, &vk.gamma_g2);
+        write_g1(&mut header, &vk.delta_g1);
+        write_g2(&mut header, &vk.delta_g2);
+        let mut ic = vec![];
+        vk.ic.iter().for_each(|p| write_g1(&mut ic, p));
+
+        let mut zkey = b"zkey".to_vec();
+        zkey.write_u32::<LittleEndian>(1).unwrap();
+        zkey.write_u32::<LittleEndian>(4).unwrap();
+        write_section(&mut zkey, 1, &1u32.to_le_bytes());
+        write_section(&mut zkey, 2, &header);
+        write_section(&mut zkey, 3, &ic);
+        // a proving key section, which is skipped
+        write_section(&mut zkey, 5, &[0u8; 64]);
+        zkey
+    }
+
+    fn load_vk_from_zkey_bytes(zkey: Vec<u8>, name: &str) -> Result<VerifyingKey<Bn256>> {
+        let zkey_file =
+            std::env::temp_dir().join(format!("eigen_vk_{}_{}.zkey", name, std::process::id()));
+        std::fs::write(&zkey_file, zkey).unwrap();
+        let loaded = load_bn256_vk_from_zkey(zkey_file.to_str().unwrap());
+        std::fs::remove_file(&zkey_file).unwrap();
+        loaded
+    }
+
+    #[test]
+    fn test_load_bn256_vk_from_zkey() {
+        let g1 = |k: &str| G1Affine::one().mul(Fr::from_str(k).unwrap()).into_affine();
+        let g2 = |k: &str| G2Affine::one().mul(Fr::from_str(k).unwrap()).into_affine();
+        let vk = VerifyingKey::<Bn256> {
+            alpha_g1: g1("2"),
+            beta_g1: g1("3"),
+            beta_g2: g2("3"),
+            gamma_g2: g2("5"),
+            delta_g1: g1("7"),
+            delta_g2: g2("7"),
+            ic: vec![g1("11"), g1("13")],
+        };
+        let loaded = load_vk_from_zkey_bytes(zkey_for(&vk), "synthetic").unwrap();
+        assert_eq!(loaded, vk);
+    }
+
+    #[test]
+    fn test_zkey_vk_verifies_proof() {
+        use crate::bellman_ce::groth16::{
+            create_proof, generate_parameters, prepare_verifying_key, verify_proof,
+        };
+        use crate::circom_circuit::CircomCircuit;
+        use crate::witness::WitnessCalculator;
+        use crate::witness::{calculate_witness_element, load_input_for_witness};
+        let root = concat!(env!("CARGO_MANIFEST_DIR"), "/../test/");
+        let fr = |v: &str| Fr::from_str(v).unwrap();
+
+        let mut wtns = WitnessCalculator::from_file(format!("{}multiplier.wasm", root)).unwrap();
+        let witness = wtns
+            .calculate_witness(
+                load_input_for_witness(&format!("{}multiplier.input.json", root)),
+                true,
+            )
+            .unwrap()
+            .iter()
+            .map(|w| calculate_witness_element::<Bn256>(w).unwrap())
+            .collect::<Vec<_>>();
+        let circuit = CircomCircuit {
+            r1cs: load_r1cs::<Bn256>(&format!("{}multiplier.r1cs", root)),
+            witness: Some(witness),
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+
+        // fixed toxic waste and blinding factors, no rng needed for a test
+        let params = generate_parameters::<Bn256, _>(
+            circuit.clone(),
+            G1Affine::one().into_projective(),
+            G2Affine::one().into_projective(),
+            fr("3"),
+            fr("5"),
+            fr("7"),
+            fr("11"),
+            fr("13"),
+        )
+        .unwrap();
+        let proof = create_proof(circuit.clone(), &params, fr("17"), fr("19")).unwrap();
+        let inputs = circuit.get_public_inputs().unwrap();
+
+        let loaded = load_vk_from_zkey_bytes(zkey_for(&params.vk), "multiplier").unwrap();
+        assert_eq!(loaded, params.vk);
+        let pvk = prepare_verifying_key(&loaded);
+        assert!(verify_proof(&pvk, &proof, &inputs).unwrap());
+        let mut tampered = inputs.clone();
+        tampered[0].add_assign(&Fr::one());
+        assert!(!verify_proof(&pvk, &proof, &tampered).unwrap());
+    }
+
+    #[test]
+    fn test_load_r1cs_for() {
+        use crate::bellman_ce::pairing::bls12_381::Bls12;
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../test/multiplier.r1cs");
+        let r1cs = load_r1cs_for::<Bn256>(path).unwrap();
+        assert_eq!(
+            r1cs.constraints.len(),
+            load_r1cs::<Bn256>(path).constraints.len()
+        );
+
+        let err = load_r1cs_for::<Bls12>(path).unwrap_err();
+        match err.downcast_ref::<EigenError>() {
+            Some(EigenError::CurveMismatch { found, .. }) => assert_eq!(
+                found,
+                "21888242871839275222246405745257275088548364400416034343698204186575808495617"
+            ),
+            _ => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_validate_wtns_file() {
+        let mut header = vec![];
+        header.write_u32::<LittleEndian>(32).unwrap();
+        Fr::char().write_le(&mut header).unwrap();
+        header.write_u32::<LittleEndian>(3).unwrap();
+        let mut witness = vec![];
+        for v in ["1", "33", "3"] {
+            Fr::from_str(v)
+                .unwrap()
+                .into_repr()
+                .write_le(&mut witness)
+                .unwrap();
+        }
+        let mut wtns = b"wtns".to_vec();
+        wtns.write_u32::<LittleEndian>(2).unwrap();
+        wtns.write_u32::<LittleEndian>(2).unwrap();
+        write_section(&mut wtns, 1, &header);
+        write_section(&mut wtns, 2, &witness);
+
+        let path = std::env::temp_dir().join(format!("eigen_validate_{}.wtns", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, &wtns).unwrap();
+        let stats = validate_wtns_file(path);
+        assert_eq!(load_witness_from_bin_file::<Bn256>(path).len(), 3);
+        std::fs::write(path, &wtns[..wtns.len() - 10]).unwrap();
+        let truncated = validate_wtns_file(path);
+        std::fs::remove_file(path).unwrap();
+
+        let stats = stats.unwrap();
+        assert_eq!(stats.witness_size, 3);
+        assert_eq!(stats.field_size, 32);
+        assert_eq!(stats.version, 2);
+        let err = truncated.unwrap_err().to_string();
+        assert!(err.contains("truncated"), "{}", err);
+    }
+}
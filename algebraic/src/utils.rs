@@ -1,7 +1,8 @@
 use crate::bellman_ce::pairing::{ff::PrimeField, Engine};
 use franklin_crypto::plonk::circuit::bigint::field::RnsParameters;
+use num_bigint::BigInt;
 pub use num_bigint::BigUint;
-use num_traits::Num;
+use num_traits::{Num, One, Zero};
 use std::fmt::Display;
 
 //export some more funcs
@@ -133,6 +134,120 @@ pub fn witness_to_field<E: Engine, F: PrimeField>(
     }
 }
 
+/// Size and FFT friendliness of a candidate prime
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub bits: u64,
+    /// largest `s` such that `2^s` divides `p - 1`, i.e. the biggest radix-2 domain is `2^s`
+    pub two_adicity: u32,
+    pub is_probably_prime: bool,
+}
+
+pub fn field_info(prime: &BigInt) -> FieldInfo {
+    let one = BigInt::one();
+    let two = BigInt::from(2u32);
+    let mut two_adicity = 0;
+    if prime > &one {
+        let mut q = prime - &one;
+        while (&q % &two).is_zero() {
+            q /= &two;
+            two_adicity += 1;
+        }
+    }
+    FieldInfo {
+        bits: prime.bits(),
+        two_adicity,
+        is_probably_prime: is_probably_prime(prime),
+    }
+}
+
+// Miller-Rabin with the first 12 primes as bases, deterministic below 3.3 * 10^24
+fn is_probably_prime(n: &BigInt) -> bool {
+    const BASES: [u32; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    let one = BigInt::one();
+    let two = BigInt::from(2u32);
+    if n < &two {
+        return false;
+    }
+    for b in BASES {
+        let b = BigInt::from(b);
+        if n == &b {
+            return true;
+        }
+        if (n % &b).is_zero() {
+            return false;
+        }
+    }
+
+    let n_1 = n - &one;
+    let mut d = n_1.clone();
+    let mut s = 0;
+    while (&d % &two).is_zero() {
+        d /= &two;
+        s += 1;
+    }
+    'witness: for b in BASES {
+        let mut x = BigInt::from(b).modpow(&d, n);
+        if x == one || x == n_1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = x.modpow(&two, n);
+            if x == n_1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+/// The curve a circuit was compiled for, by the prime of its scalar field
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CurveKind {
+    Bn128,
+    Bls12_381,
+    Unknown(BigInt),
+}
+
+impl CurveKind {
+    pub fn from_prime(prime: &BigInt) -> Self {
+        const BN128: &str =
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617";
+        const BLS12_381: &str =
+            "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+        match prime.to_str_radix(10).as_str() {
+            BN128 => CurveKind::Bn128,
+            BLS12_381 => CurveKind::Bls12_381,
+            _ => CurveKind::Unknown(prime.clone()),
+        }
+    }
+}
+
+/// Detect the curve of a circom `.r1cs` or `.wasm` from the prime it declares, the format
+/// is told by the file's magic number, not its extension.
+pub fn detect_curve(path: impl AsRef<std::path::Path>) -> anyhow::Result<CurveKind> {
+    use std::io::{Read, Seek, SeekFrom};
+    let path = path.as_ref();
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| anyhow::anyhow!("Open {}, {:?}", path.display(), e))?;
+    let mut magic = [0u8; 4];
+    file.read_exact(&mut magic)?;
+    let prime = match &magic {
+        b"r1cs" => {
+            file.seek(SeekFrom::Start(0))?;
+            let header = crate::r1cs_file::header_from_reader(std::io::BufReader::new(file))?;
+            BigInt::from_bytes_le(num_bigint::Sign::Plus, &header.prime_size)
+        }
+        b"\0asm" => crate::witness::WitnessCalculator::from_file(path)?
+            .memory
+            .prime
+            .clone(),
+        _ => anyhow::bail!("{} is neither an r1cs nor a wasm file", path.display()),
+    };
+    Ok(CurveKind::from_prime(&prime))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -177,4 +292,47 @@ mod tests {
         let fq_restored = witness_to_field(&wts[..], &rns_params);
         assert_eq!(fq, fq_restored);
     }
+
+    #[test]
+    fn test_detect_curve() {
+        let path = |p: &str| format!("{}/{}", env!("CARGO_MANIFEST_DIR"), p);
+        for file in ["../test/multiplier.r1cs", "test-vectors/mycircuit.wasm"] {
+            assert_eq!(detect_curve(path(file)).unwrap(), CurveKind::Bn128);
+        }
+        for file in [
+            "../groth16/test-vectors/mycircuit_bls12381.r1cs",
+            "../groth16/test-vectors/mycircuit_bls12381.wasm",
+        ] {
+            assert_eq!(detect_curve(path(file)).unwrap(), CurveKind::Bls12_381);
+        }
+        assert_eq!(
+            CurveKind::from_prime(&BigInt::from(0xFFFFFFFF00000001u64)),
+            CurveKind::Unknown(BigInt::from(0xFFFFFFFF00000001u64))
+        );
+        assert!(detect_curve(path("../test/multiplier.input.json")).is_err());
+    }
+
+    #[test]
+    fn test_field_info() {
+        let bn254 = BigInt::from_str_radix(
+            "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+            10,
+        )
+        .unwrap();
+        assert_eq!(
+            field_info(&bn254),
+            FieldInfo {
+                bits: 254,
+                two_adicity: 28,
+                is_probably_prime: true,
+            }
+        );
+
+        let goldilocks = BigInt::from(0xFFFFFFFF00000001u64);
+        assert_eq!(field_info(&goldilocks).two_adicity, 32);
+        assert!(field_info(&goldilocks).is_probably_prime);
+
+        assert!(!field_info(&(&bn254 * BigInt::from(3u32))).is_probably_prime);
+        assert!(!field_info(&BigInt::from(561u32)).is_probably_prime);
+    }
 }
@@ -5,6 +5,12 @@ use wasmer::{Function, Instance, Store, Value};
 #[derive(Clone, Debug)]
 pub struct Wasm(Instance);
 
+#[cfg(test)]
+thread_local! {
+    // `init` calls on this thread, for tests checking a witness initializes the instance once
+    pub(crate) static INIT_CALLS: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
 impl Wasm {
     pub(crate) fn get_field_num_len32(&self, store: &mut Store) -> Result<u32> {
         self.get_u32(store, "getFieldNumLen32")
@@ -40,6 +46,20 @@ impl Wasm {
         Ok(())
     }
 
+    /// The number of elements of the input signal hashed to `hmsb`, `hlsb`, `None` if the
+    /// circuit has no such input.
+    pub(crate) fn get_input_signal_size(
+        &self,
+        store: &mut Store,
+        hmsb: u32,
+        hlsb: u32,
+    ) -> Option<u32> {
+        let func = self.func("getInputSignalSize");
+        // unknown signals raise exception 1, which traps
+        let result = func.call(store, &[hmsb.into(), hlsb.into()]).ok()?;
+        Some(result[0].unwrap_i32() as u32)
+    }
+
     pub(crate) fn get_witness(&self, store: &mut Store, i: u32) -> Result<()> {
         let func = self.func("getWitness");
         func.call(store, &[i.into()])?;
@@ -51,6 +71,8 @@ impl Wasm {
     }
 
     pub(crate) fn init(&self, store: &mut Store, sanity_check: bool) -> Result<()> {
+        #[cfg(test)]
+        INIT_CALLS.with(|c| c.set(c.get() + 1));
         let func = self.func("init");
         func.call(store, &[Value::I32(sanity_check as i32)])?;
         Ok(())
@@ -0,0 +1,220 @@
+//! Inspect the `.dat` file circom's C backend generates next to `<circuit>.cpp`.
+//!
+//! The `.dat` holds the input signal hash table, the witness to signal map and the
+//! constant pool, but not their sizes nor the prime, both are compiled into the generated
+//! sources of the same folder, so those are read from there.
+use anyhow::{anyhow, bail, Result};
+use byteorder::{LittleEndian, ReadBytesExt};
+use num_bigint::BigInt;
+use num_traits::One;
+use std::io::Read;
+use std::path::Path;
+
+const HASHMAP_SIZE: usize = 256;
+const FR_LONG: u32 = 0x80000000;
+const FR_MONTGOMERY: u32 = 0x40000000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InputHashEntry {
+    pub hash: u64,
+    pub signal_id: u64,
+    pub signal_size: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct DatInfo {
+    /// the field prime, `None` if it couldn't be found in the generated `fr` sources
+    pub prime: Option<BigInt>,
+    pub n64: usize,
+    /// occupied slots of the input signal hash table
+    pub inputs: Vec<InputHashEntry>,
+    pub witness_to_signal: Vec<u64>,
+    pub constants: Vec<BigInt>,
+    /// bytes left for the io map
+    pub io_map_size: usize,
+}
+
+pub fn read_dat_file(path: impl AsRef<Path>) -> Result<DatInfo> {
+    let path = path.as_ref();
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let cpp = std::fs::read_to_string(path.with_extension("cpp"))
+        .map_err(|e| anyhow!("Open {}, {:?}", path.with_extension("cpp").display(), e))?;
+    let witness_size = generated_size(&cpp, "get_size_of_witness")?;
+    let constants_size = generated_size(&cpp, "get_size_of_constants")?;
+
+    let fr_hpp = std::fs::read_to_string(dir.join("fr.hpp")).unwrap_or_default();
+    let n64 = fr_hpp
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("#define Fr_N64"))
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(4);
+    let prime = ["fr.cpp", "fr.asm"]
+        .iter()
+        .filter_map(|f| std::fs::read_to_string(dir.join(f)).ok())
+        .find_map(|src| find_prime(&src));
+
+    let data = std::fs::read(path).map_err(|e| anyhow!("Open {}, {:?}", path.display(), e))?;
+    let mut reader = &data[..];
+    let mut inputs = vec![];
+    for _ in 0..HASHMAP_SIZE {
+        let entry = InputHashEntry {
+            hash: reader.read_u64::<LittleEndian>()?,
+            signal_id: reader.read_u64::<LittleEndian>()?,
+            signal_size: reader.read_u64::<LittleEndian>()?,
+        };
+        if entry.hash != 0 {
+            inputs.push(entry);
+        }
+    }
+    let witness_to_signal = (0..witness_size)
+        .map(|_| reader.read_u64::<LittleEndian>())
+        .collect::<std::io::Result<Vec<_>>>()?;
+    let mut constants = Vec::with_capacity(constants_size);
+    for _ in 0..constants_size {
+        constants.push(read_fr_element(&mut reader, n64, prime.as_ref())?);
+    }
+
+    Ok(DatInfo {
+        prime,
+        n64,
+        inputs,
+        witness_to_signal,
+        constants,
+        io_map_size: reader.len(),
+    })
+}
+
+// the generated sources define e.g. `uint get_size_of_witness() {return 4;}`
+fn generated_size(cpp: &str, func: &str) -> Result<usize> {
+    let start = cpp
+        .find(func)
+        .ok_or_else(|| anyhow!("{} not found in the generated cpp", func))?;
+    let body = &cpp[start..];
+    let body = &body[(body
+        .find("return")
+        .ok_or_else(|| anyhow!("invalid {}", func))?
+        + 6)..];
+    let end = body.find(';').ok_or_else(|| anyhow!("invalid {}", func))?;
+    Ok(body[..end].trim().parse()?)
+}
+
+// `Fr_rawq = {0x..,0x..}` in fr.cpp, or `Fr_q: dd 0 dd 0x80000000 dq 0x..,0x..` in fr.asm,
+// both with the least significant limb first
+fn find_prime(src: &str) -> Option<BigInt> {
+    let limbs = if let Some(i) = src.find("Fr_rawq") {
+        let rest = &src[i..];
+        let rest = &rest[(rest.find('{')? + 1)..];
+        &rest[..rest.find('}')?]
+    } else {
+        let rest = &src[src.find("Fr_q:")?..];
+        let rest = &rest[(rest.find("dq")? + 2)..];
+        rest.lines().next()?
+    };
+    let mut prime = BigInt::from(0u32);
+    for limb in limbs.split(',').map(str::trim).rev() {
+        let limb = u64::from_str_radix(limb.strip_prefix("0x")?, 16).ok()?;
+        prime = (prime << 64) + BigInt::from(limb);
+    }
+    Some(prime)
+}
+
+// FrElement { int32_t shortVal; uint32_t type; uint64_t longVal[Fr_N64]; }
+fn read_fr_element<R: Read>(reader: &mut R, n64: usize, prime: Option<&BigInt>) -> Result<BigInt> {
+    let short_val = reader.read_i32::<LittleEndian>()?;
+    let ty = reader.read_u32::<LittleEndian>()?;
+    let mut value = BigInt::from(0u32);
+    let mut limbs = vec![0u64; n64];
+    for limb in limbs.iter_mut() {
+        *limb = reader.read_u64::<LittleEndian>()?;
+    }
+    if ty & FR_LONG == 0 {
+        return Ok(BigInt::from(short_val));
+    }
+    for limb in limbs.iter().rev() {
+        value = (value << 64) + BigInt::from(*limb);
+    }
+    if ty & FR_MONTGOMERY != 0 {
+        let p = match prime {
+            Some(p) => p,
+            None => bail!("montgomery constant without a known prime"),
+        };
+        // value * R^-1 mod p, with R = 2^(64 * n64)
+        let r = (BigInt::one() << (64 * n64)) % p;
+        let r_inv = r.modpow(&(p - BigInt::from(2u32)), p);
+        value = (value * r_inv) % p;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::WriteBytesExt;
+    use num_traits::Num;
+
+    #[test]
+    fn test_read_dat_file() {
+        let dir = std::env::temp_dir().join(format!("eigen_dat_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("multiplier.cpp"),
+            "uint get_size_of_witness() {return 4;}\nuint get_size_of_constants() {return 2;}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("fr.hpp"), "#define Fr_N64 4\n").unwrap();
+        std::fs::write(
+            dir.join("fr.cpp"),
+            "FrRawElement Fr_rawq = {0x43e1f593f0000001,0x2833e84879b97091,0xb85045b68181585d,0x30644e72e131a029};\n",
+        )
+        .unwrap();
+
+        let mut dat = vec![];
+        for i in 0..HASHMAP_SIZE {
+            let entry: [u64; 3] = match i {
+                3 => [0xaabbccdd, 2, 1],
+                7 => [0x11223344, 3, 1],
+                _ => [0, 0, 0],
+            };
+            for v in entry {
+                dat.write_u64::<LittleEndian>(v).unwrap();
+            }
+        }
+        for w in [0u64, 1, 2, 3] {
+            dat.write_u64::<LittleEndian>(w).unwrap();
+        }
+        // short 7, then long 2^64 + 5
+        dat.write_i32::<LittleEndian>(7).unwrap();
+        dat.write_u32::<LittleEndian>(0).unwrap();
+        dat.extend([0u8; 32]);
+        dat.write_i32::<LittleEndian>(0).unwrap();
+        dat.write_u32::<LittleEndian>(FR_LONG).unwrap();
+        for limb in [5u64, 1, 0, 0] {
+            dat.write_u64::<LittleEndian>(limb).unwrap();
+        }
+        std::fs::write(dir.join("multiplier.dat"), dat).unwrap();
+
+        let info = read_dat_file(dir.join("multiplier.dat"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        let info = info.unwrap();
+        assert_eq!(
+            info.prime.unwrap(),
+            BigInt::from_str_radix(
+                "21888242871839275222246405745257275088548364400416034343698204186575808495617",
+                10
+            )
+            .unwrap()
+        );
+        assert_eq!(info.n64, 4);
+        assert_eq!(info.inputs.len(), 2);
+        assert_eq!(info.inputs[1].signal_id, 3);
+        assert_eq!(info.witness_to_signal, vec![0, 1, 2, 3]);
+        assert_eq!(
+            info.constants,
+            vec![
+                BigInt::from(7u32),
+                (BigInt::one() << 64) + BigInt::from(5u32)
+            ]
+        );
+        assert_eq!(info.io_map_size, 0);
+    }
+}
@@ -0,0 +1,247 @@
+use crate::errors::EigenError;
+use crate::witness::witness_calculator::{escape_pointer, flatten_input};
+use anyhow::Result;
+use num_bigint::BigInt;
+use serde_json::Value;
+use std::io::{BufReader, Bytes, Read};
+use std::iter::Peekable;
+
+/// Lazily split a top-level JSON input object into `(signal, values)` pairs.
+///
+/// Only one signal is buffered at a time, so circuits with huge array inputs needn't
+/// materialize the whole map via `load_input_for_witness`. A read error or malformed JSON is
+/// yielded as an `EigenError::InputValue` and ends the stream.
+pub struct InputStream<R: Read> {
+    bytes: Peekable<Bytes<BufReader<R>>>,
+    started: bool,
+    done: bool,
+}
+
+fn invalid(path: &str, reason: impl Into<String>) -> EigenError {
+    EigenError::InputValue {
+        path: path.to_string(),
+        reason: reason.into(),
+    }
+}
+
+impl<R: Read> InputStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            bytes: BufReader::new(reader).bytes().peekable(),
+            started: false,
+            done: false,
+        }
+    }
+
+    fn next_byte(&mut self) -> std::result::Result<Option<u8>, EigenError> {
+        self.bytes
+            .next()
+            .transpose()
+            .map_err(|e| invalid("", format!("unable to read input: {}", e)))
+    }
+
+    fn peek_byte(&mut self) -> std::result::Result<Option<u8>, EigenError> {
+        match self.bytes.peek() {
+            Some(Ok(b)) => Ok(Some(*b)),
+            Some(Err(_)) => self.next_byte(),
+            None => Ok(None),
+        }
+    }
+
+    fn next_non_ws(&mut self) -> std::result::Result<Option<u8>, EigenError> {
+        while let Some(b) = self.next_byte()? {
+            if !b.is_ascii_whitespace() {
+                return Ok(Some(b));
+            }
+        }
+        Ok(None)
+    }
+
+    // the opening quote is already in `buf`
+    fn read_string(&mut self, buf: &mut Vec<u8>) -> std::result::Result<(), EigenError> {
+        let mut escaped = false;
+        loop {
+            let b = self
+                .next_byte()?
+                .ok_or_else(|| invalid("", "unterminated string"))?;
+            buf.push(b);
+            match b {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    fn read_value(&mut self, path: &str) -> std::result::Result<Vec<u8>, EigenError> {
+        let first = self
+            .next_non_ws()?
+            .ok_or_else(|| invalid(path, "missing value"))?;
+        let mut buf = vec![first];
+        match first {
+            b'"' => self.read_string(&mut buf)?,
+            b'[' | b'{' => {
+                let mut depth = 1;
+                while depth > 0 {
+                    let b = self
+                        .next_byte()?
+                        .ok_or_else(|| invalid(path, "unterminated value"))?;
+                    buf.push(b);
+                    match b {
+                        b'"' => self.read_string(&mut buf)?,
+                        b'[' | b'{' => depth += 1,
+                        b']' | b'}' => depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {
+                while let Some(b) = self.peek_byte()? {
+                    if b == b',' || b == b'}' || b.is_ascii_whitespace() {
+                        break;
+                    }
+                    buf.push(b);
+                    self.next_byte()?;
+                }
+            }
+        }
+        Ok(buf)
+    }
+
+    // `None` at the end of the object
+    fn next_signal(&mut self) -> std::result::Result<Option<(String, Vec<BigInt>)>, EigenError> {
+        let mut c = self.next_non_ws()?;
+        if !self.started {
+            if c != Some(b'{') {
+                return Err(invalid("", "expected an object"));
+            }
+            self.started = true;
+            c = self.next_non_ws()?;
+        } else if c == Some(b',') {
+            c = self.next_non_ws()?;
+        }
+        match c {
+            Some(b'}') => return Ok(None),
+            Some(b'"') => {}
+            _ => return Err(invalid("", "expected a signal name")),
+        }
+
+        let mut key = vec![b'"'];
+        self.read_string(&mut key)?;
+        let key: String =
+            serde_json::from_slice(&key).map_err(|e| invalid("", format!("signal name, {}", e)))?;
+        let path = format!("/{}", escape_pointer(&key));
+        if self.next_non_ws()? != Some(b':') {
+            return Err(invalid(&path, "expected ':' after the signal name"));
+        }
+        let value: Value = serde_json::from_slice(&self.read_value(&path)?)
+            .map_err(|e| invalid(&path, e.to_string()))?;
+        let values = flatten_input(&key, &value)?;
+        Ok(Some((key, values)))
+    }
+}
+
+impl<R: Read> Iterator for InputStream<R> {
+    type Item = Result<(String, Vec<BigInt>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_signal() {
+            Ok(Some(signal)) => Some(Ok(signal)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::witness::{load_input_for_witness, WitnessCalculator};
+    use std::path::PathBuf;
+
+    fn root_path(p: &str) -> String {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push(p);
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_stream_inputs() {
+        let json = r#" { "a": [1, ["2", true]], "b\"c" : 7 ,"d":"5"} "#;
+        let inputs = InputStream::new(json.as_bytes())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            inputs,
+            vec![
+                (
+                    "a".to_string(),
+                    vec![BigInt::from(1u32), BigInt::from(2u32), BigInt::from(1u32)]
+                ),
+                ("b\"c".to_string(), vec![BigInt::from(7u32)]),
+                ("d".to_string(), vec![BigInt::from(5u32)]),
+            ]
+        );
+        assert_eq!(InputStream::new("{}".as_bytes()).count(), 0);
+    }
+
+    #[test]
+    fn test_stream_inputs_witness() {
+        let input_file = root_path("../test/multiplier.input.json");
+        let mut wtns = WitnessCalculator::from_file(&root_path("../test/multiplier.wasm")).unwrap();
+        let eager = wtns
+            .calculate_witness(load_input_for_witness(&input_file), false)
+            .unwrap();
+        let file = std::fs::File::open(&input_file).unwrap();
+        let inputs = InputStream::new(file).collect::<Result<Vec<_>>>().unwrap();
+        let streamed = wtns.calculate_witness(inputs, false).unwrap();
+        assert_eq!(eager, streamed);
+    }
+
+    #[test]
+    fn test_stream_inputs_malformed() {
+        for json in [
+            r#"["a", 1]"#,
+            r#"{"a": 1, 2}"#,
+            r#"{"a" 1}"#,
+            r#"{"a": [1, 2"#,
+            r#"{"a": "1"#,
+            r#"{"a": tru}"#,
+            r#"{"a": "x"}"#,
+        ] {
+            let mut stream = InputStream::new(json.as_bytes());
+            let err = stream
+                .find_map(|r| r.err())
+                .unwrap_or_else(|| panic!("{} parsed", json));
+            assert!(
+                matches!(
+                    err.downcast_ref::<EigenError>(),
+                    Some(EigenError::InputValue { .. })
+                ),
+                "{}: {}",
+                json,
+                err
+            );
+            assert!(stream.next().is_none(), "{}", json);
+        }
+
+        // the signals before the error still come out
+        let mut stream = InputStream::new(r#"{"a": 1, "b": [}"#.as_bytes());
+        assert_eq!(
+            stream.next().unwrap().unwrap(),
+            ("a".to_string(), vec![BigInt::from(1u32)])
+        );
+        assert!(stream.next().unwrap().is_err());
+        assert!(stream.next().is_none());
+    }
+}
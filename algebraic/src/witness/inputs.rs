@@ -0,0 +1,182 @@
+use crate::errors::EigenError;
+use anyhow::{bail, Result};
+use num_bigint::BigInt;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Visibility {
+    Public,
+    Private,
+}
+
+/// An input of the circuit's main component, as `WitnessCalculator::circuit_inputs` reports it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InputSignal {
+    pub name: String,
+    /// number of field elements
+    pub size: usize,
+    pub visibility: Visibility,
+}
+
+/// Builder for the inputs fed into `WitnessCalculator::calculate_witness`,
+/// keeping track of which signals are public and which are private.
+#[derive(Clone, Debug, Default)]
+pub struct Inputs {
+    signals: Vec<(String, Vec<BigInt>, Visibility)>,
+}
+
+impl Inputs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn public(mut self, name: &str, values: Vec<BigInt>) -> Self {
+        self.signals
+            .push((name.to_string(), values, Visibility::Public));
+        self
+    }
+
+    pub fn private(mut self, name: &str, values: Vec<BigInt>) -> Self {
+        self.signals
+            .push((name.to_string(), values, Visibility::Private));
+        self
+    }
+
+    pub fn visibility(&self, name: &str) -> Option<Visibility> {
+        self.signals
+            .iter()
+            .find(|(n, _, _)| n == name)
+            .map(|(_, _, v)| *v)
+    }
+
+    /// Check the signals against the inputs of `circuit`: each one must be an input of the
+    /// circuit, with as many values and the same visibility, and every input of the circuit
+    /// must be given. Then return the inputs in the shape expected by `calculate_witness`.
+    pub fn build(self, circuit: &[InputSignal]) -> Result<Vec<(String, Vec<BigInt>)>> {
+        for (name, values, visibility) in self.signals.iter() {
+            let signal = match circuit.iter().find(|s| &s.name == name) {
+                Some(signal) => signal,
+                None => bail!(EigenError::InputSignal {
+                    name: name.clone(),
+                    expected: 0,
+                    got: values.len(),
+                }),
+            };
+            if signal.size != values.len() {
+                bail!(EigenError::InputSignal {
+                    name: name.clone(),
+                    expected: signal.size,
+                    got: values.len(),
+                });
+            }
+            if signal.visibility != *visibility {
+                bail!(
+                    "input {} is {:?} in the circuit, given as {:?}",
+                    name,
+                    signal.visibility,
+                    visibility
+                );
+            }
+        }
+        let missing = circuit
+            .iter()
+            .filter(|s| !self.signals.iter().any(|(n, _, _)| n == &s.name))
+            .map(|s| s.name.as_str())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            bail!("missing input: {}", missing.join(", "));
+        }
+        Ok(self
+            .signals
+            .into_iter()
+            .map(|(name, values, _)| (name, values))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn circuit() -> Vec<InputSignal> {
+        vec![
+            InputSignal {
+                name: "a".to_string(),
+                size: 1,
+                visibility: Visibility::Public,
+            },
+            InputSignal {
+                name: "b".to_string(),
+                size: 2,
+                visibility: Visibility::Private,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_build_inputs() {
+        let inputs = Inputs::new()
+            .public("a", vec![BigInt::from(3u32)])
+            .private("b", vec![BigInt::from(11u32), BigInt::from(5u32)]);
+        assert_eq!(inputs.visibility("a"), Some(Visibility::Public));
+        assert_eq!(inputs.visibility("b"), Some(Visibility::Private));
+
+        let built = inputs.build(&circuit()).unwrap();
+        assert_eq!(built.len(), 2);
+        assert_eq!(
+            built[1],
+            (
+                "b".to_string(),
+                vec![BigInt::from(11u32), BigInt::from(5u32)]
+            )
+        );
+    }
+
+    #[test]
+    fn test_build_inputs_missing() {
+        let inputs = Inputs::new().public("a", vec![BigInt::from(3u32)]);
+        let err = inputs.build(&circuit()).unwrap_err();
+        assert_eq!(err.to_string(), "missing input: b");
+    }
+
+    #[test]
+    fn test_build_inputs_unknown() {
+        let b = vec![BigInt::from(11u32), BigInt::from(5u32)];
+        let inputs = Inputs::new()
+            .public("a", vec![BigInt::from(3u32)])
+            .private("b", b.clone())
+            .private("z", vec![BigInt::from(1u32)]);
+        let err = inputs.build(&circuit()).unwrap_err();
+        match err.downcast_ref::<EigenError>() {
+            Some(EigenError::InputSignal {
+                name,
+                expected,
+                got,
+            }) => assert_eq!((name.as_str(), *expected, *got), ("z", 0, 1)),
+            _ => panic!("unexpected error {:?}", err),
+        }
+
+        // too few values for `b`
+        let inputs = Inputs::new()
+            .public("a", vec![BigInt::from(3u32)])
+            .private("b", b[..1].to_vec());
+        let err = inputs.build(&circuit()).unwrap_err();
+        match err.downcast_ref::<EigenError>() {
+            Some(EigenError::InputSignal {
+                name,
+                expected,
+                got,
+            }) => assert_eq!((name.as_str(), *expected, *got), ("b", 2, 1)),
+            _ => panic!("unexpected error {:?}", err),
+        }
+
+        // `a` is public in the circuit
+        let inputs = Inputs::new()
+            .private("a", vec![BigInt::from(3u32)])
+            .private("b", b);
+        let err = inputs.build(&circuit()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "input a is Public in the circuit, given as Private"
+        );
+    }
+}
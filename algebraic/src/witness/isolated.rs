@@ -0,0 +1,122 @@
+use crate::witness::WitnessCalculator;
+use anyhow::{bail, Result};
+use num_bigint::BigInt;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::FromRawFd;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::str::FromStr;
+
+const EXIT_OK: i32 = 0;
+const EXIT_ERROR: i32 = 1;
+const EXIT_PANIC: i32 = 2;
+
+impl WitnessCalculator {
+    /// Same as `calculate_witness`, but the wasm runs in a forked child process and the
+    /// witness comes back over a pipe, so a panic or crash inside the runtime turns into an
+    /// error here instead of taking the whole process down.
+    ///
+    /// The child only gets a copy of the calling thread, avoid calling this while other
+    /// threads hold locks the witness calculation needs.
+    pub fn calculate_witness_isolated<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<BigInt>> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            bail!("pipe failed: {}", std::io::Error::last_os_error());
+        }
+        let (read_fd, write_fd) = (fds[0], fds[1]);
+
+        let pid = unsafe { libc::fork() };
+        if pid < 0 {
+            unsafe {
+                libc::close(read_fd);
+                libc::close(write_fd);
+            }
+            bail!("fork failed: {}", std::io::Error::last_os_error());
+        }
+
+        if pid == 0 {
+            // child: never return into the caller's stack
+            unsafe { libc::close(read_fd) };
+            let mut writer = unsafe { File::from_raw_fd(write_fd) };
+            let code = match catch_unwind(AssertUnwindSafe(|| {
+                self.calculate_witness(inputs, sanity_check)
+            })) {
+                Ok(Ok(w)) => {
+                    let out = w.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                    match writer.write_all(out.join("\n").as_bytes()) {
+                        Ok(_) => EXIT_OK,
+                        Err(_) => EXIT_ERROR,
+                    }
+                }
+                Ok(Err(e)) => {
+                    let _ = writer.write_all(e.to_string().as_bytes());
+                    EXIT_ERROR
+                }
+                Err(_) => EXIT_PANIC,
+            };
+            drop(writer);
+            unsafe { libc::_exit(code) };
+        }
+
+        unsafe { libc::close(write_fd) };
+        let mut reader = unsafe { File::from_raw_fd(read_fd) };
+        let mut out = String::new();
+        let read_result = reader.read_to_string(&mut out);
+
+        let mut status = 0;
+        if unsafe { libc::waitpid(pid, &mut status, 0) } < 0 {
+            bail!("waitpid failed: {}", std::io::Error::last_os_error());
+        }
+        if libc::WIFSIGNALED(status) {
+            bail!(
+                "witness calculation crashed with signal {}",
+                libc::WTERMSIG(status)
+            );
+        }
+        match libc::WEXITSTATUS(status) {
+            EXIT_OK => {}
+            EXIT_ERROR => bail!("witness calculation failed: {}", out),
+            EXIT_PANIC => bail!("witness calculation panicked"),
+            code => bail!("witness calculation exited with code {}", code),
+        }
+        read_result?;
+
+        out.split('\n')
+            .map(|v| Ok(BigInt::from_str(v)?))
+            .collect::<Result<Vec<_>>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn root_path(p: &str) -> String {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push(p);
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_calculate_witness_isolated() {
+        let mut wtns =
+            WitnessCalculator::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let inputs = vec![
+            ("a".to_string(), vec![BigInt::from(3u32)]),
+            ("b".to_string(), vec![BigInt::from(11u32)]),
+        ];
+        let w = wtns
+            .calculate_witness_isolated(inputs.clone(), false)
+            .unwrap();
+        assert_eq!(w, wtns.calculate_witness(inputs, false).unwrap());
+
+        // unknown signal traps inside the wasm runtime
+        let bad_inputs = vec![("c".to_string(), vec![BigInt::from(3u32)])];
+        assert!(wtns.calculate_witness_isolated(bad_inputs, false).is_err());
+    }
+}
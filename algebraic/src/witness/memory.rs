@@ -1,7 +1,8 @@
 //! Safe-ish interface for reading and writing specific types to the WASM runtime's memory,
 //! modified from ark-circom
+use crate::errors::EigenError;
 use num_bigint::BigInt;
-use wasmer::Memory;
+use wasmer::{AsStoreRef, Memory};
 
 #[derive(Clone, Debug)]
 pub struct SafeMemory {
@@ -39,4 +40,45 @@ impl SafeMemory {
             // n32,
         }
     }
+
+    /// Bytes of linear memory currently allocated to the instance.
+    pub fn size(&self, store: &impl AsStoreRef) -> u64 {
+        self.memory.view(store).data_size()
+    }
+
+    /// Check `len` bytes at `offset` lie within the allocated memory.
+    pub fn check_bounds(
+        &self,
+        store: &impl AsStoreRef,
+        offset: u64,
+        len: u64,
+    ) -> Result<(), EigenError> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.size(store) => Ok(()),
+            _ => Err(EigenError::MemoryOutOfBounds { offset, len }),
+        }
+    }
+
+    pub fn read_u32(&self, store: &impl AsStoreRef, offset: u64) -> Result<u32, EigenError> {
+        self.check_bounds(store, offset, 4)?;
+        let mut buf = [0u8; 4];
+        self.memory
+            .view(store)
+            .read(offset, &mut buf)
+            .map_err(|_| EigenError::MemoryOutOfBounds { offset, len: 4 })?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    pub fn write_u32(
+        &self,
+        store: &impl AsStoreRef,
+        offset: u64,
+        value: u32,
+    ) -> Result<(), EigenError> {
+        self.check_bounds(store, offset, 4)?;
+        self.memory
+            .view(store)
+            .write(offset, &value.to_le_bytes())
+            .map_err(|_| EigenError::MemoryOutOfBounds { offset, len: 4 })
+    }
 }
@@ -1,16 +1,31 @@
 mod circom;
+mod dat;
+mod input_stream;
+mod inputs;
+#[cfg(all(unix, feature = "isolated"))]
+mod isolated;
 pub(crate) mod memory;
+mod pool;
+mod symbols;
 pub(super) mod witness_calculator;
 
+use anyhow::{bail, Result};
+pub use dat::{read_dat_file, DatInfo, InputHashEntry};
 use fnv::FnvHasher;
+pub use input_stream::InputStream;
+pub use inputs::{InputSignal, Inputs, Visibility};
 use num_bigint::BigInt;
-use num_traits::{One, Zero};
+pub use pool::WitnessCalculatorPool;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::hash::Hasher;
-use std::str::FromStr;
+pub use symbols::{diff_public_outputs, OutputMismatch, SymbolTable};
+#[allow(deprecated)]
 pub use witness_calculator::flat_array;
-pub use witness_calculator::WitnessCalculator;
+use witness_calculator::flatten_input;
+use witness_calculator::input_to_words_le;
+pub use witness_calculator::{calculate_witness_element, try_flat_array};
+pub use witness_calculator::{WitnessCalculator, WitnessCalculatorConfig};
 
 pub(crate) fn fnv(inp: &str) -> (u32, u32) {
     let mut hasher = FnvHasher::default();
@@ -29,27 +44,32 @@ pub fn load_input_for_witness(input_file: &str) -> HashMap<String, Vec<BigInt>>
     inputs
         .iter()
         .map(|(key, value)| {
-            let res = match value {
-                Value::String(inner) => {
-                    vec![BigInt::from_str(inner).unwrap()]
-                }
-                Value::Bool(inner) => {
-                    if *inner {
-                        vec![BigInt::one()]
-                    } else {
-                        vec![BigInt::zero()]
-                    }
-                }
-                Value::Number(inner) => {
-                    vec![BigInt::from_str(&inner.to_string()).unwrap()]
-                    //vec![BigInt::from(inner.as_u64().expect("not a u32"))]
-                }
-                //Value::Array(inner) => inner.iter().cloned().map(value_to_bigint).collect(),
-                Value::Array(inner) => flat_array(inner),
-                _ => panic!("{:?}", value),
-            };
-
+            let res = flatten_input(key, value).unwrap_or_else(|e| panic!("{}", e));
             (key.clone(), res)
         })
         .collect::<std::collections::HashMap<_, _>>()
 }
+
+/// Parse witness inputs straight into the `n32` little-endian words per value that
+/// `WitnessCalculator::calculate_witness_u32` writes to the wasm memory. Negative values
+/// wrap around `prime`, as in `WitnessCalculator::calculate_witness`.
+pub fn prepare_inputs_u32(
+    inputs_json: &str,
+    prime: &BigInt,
+    n32: u32,
+) -> Result<Vec<(String, Vec<u32>)>> {
+    let inputs: HashMap<String, Value> = serde_json::from_str(inputs_json)?;
+    let mut prepared = Vec::with_capacity(inputs.len());
+    for (name, value) in inputs {
+        let values = flatten_input(&name, &value)?;
+        let mut words = Vec::with_capacity(values.len() * n32 as usize);
+        for v in values.iter() {
+            if v.bits() > 32 * n32 as u64 {
+                bail!("input {} does not fit in {} words: {}", name, n32, v);
+            }
+            words.extend(input_to_words_le(v, prime, n32 as usize));
+        }
+        prepared.push((name, words));
+    }
+    Ok(prepared)
+}
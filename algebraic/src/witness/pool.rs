@@ -0,0 +1,129 @@
+use crate::witness::WitnessCalculator;
+use anyhow::{bail, Result};
+use num_bigint::BigInt;
+use std::sync::{Condvar, Mutex};
+use wasmer::{Engine, Module, Store};
+
+/// A fixed set of witness calculators instantiated from one compiled module, to compute
+/// witnesses from several threads without compiling the wasm per request.
+///
+/// Every calculation runs circom's `init` first, which resets the instance's signals, so an
+/// instance can be reused as is once a calculation returns.
+pub struct WitnessCalculatorPool {
+    engine: Engine,
+    module: Module,
+    idle: Mutex<Vec<WitnessCalculator>>,
+    available: Condvar,
+}
+
+// returns the instance to the pool, or a fresh one if the calculation panicked halfway
+struct Checkout<'a> {
+    pool: &'a WitnessCalculatorPool,
+    wtns: Option<WitnessCalculator>,
+}
+
+impl Drop for Checkout<'_> {
+    fn drop(&mut self) {
+        let wtns = match self.wtns.take() {
+            Some(_) if std::thread::panicking() => self.pool.instantiate().ok(),
+            wtns => wtns,
+        };
+        if let Some(wtns) = wtns {
+            self.pool.idle.lock().unwrap().push(wtns);
+            self.pool.available.notify_one();
+        }
+    }
+}
+
+impl WitnessCalculatorPool {
+    pub fn new(wasm: &[u8], pool_size: usize) -> Result<Self> {
+        if pool_size == 0 {
+            bail!("the pool needs at least one instance");
+        }
+        let engine = Store::default().engine().clone();
+        let module = Module::new(&engine, wasm)?;
+        let mut pool = WitnessCalculatorPool {
+            engine,
+            module,
+            idle: Mutex::new(Vec::with_capacity(pool_size)),
+            available: Condvar::new(),
+        };
+        let instances = (0..pool_size)
+            .map(|_| pool.instantiate())
+            .collect::<Result<Vec<_>>>()?;
+        *pool.idle.get_mut().unwrap() = instances;
+        Ok(pool)
+    }
+
+    fn instantiate(&self) -> Result<WitnessCalculator> {
+        WitnessCalculator::from_store_and_module(Store::new(self.engine.clone()), &self.module)
+    }
+
+    fn checkout(&self) -> Checkout<'_> {
+        let mut idle = self.idle.lock().unwrap();
+        loop {
+            if let Some(wtns) = idle.pop() {
+                return Checkout {
+                    pool: self,
+                    wtns: Some(wtns),
+                };
+            }
+            idle = self.available.wait(idle).unwrap();
+        }
+    }
+
+    /// `WitnessCalculator::calculate_witness` on the next idle instance, blocking until
+    /// one is free
+    pub fn calculate<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        &self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<BigInt>> {
+        let mut checkout = self.checkout();
+        checkout
+            .wtns
+            .as_mut()
+            .unwrap()
+            .calculate_witness(inputs, sanity_check)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    fn root_path(p: &str) -> String {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push(p);
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_witness_calculator_pool() {
+        let wasm_file = root_path("test-vectors/mycircuit.wasm");
+        let wasm = std::fs::read(&wasm_file).unwrap();
+        assert!(WitnessCalculatorPool::new(&wasm, 0).is_err());
+        let pool = Arc::new(WitnessCalculatorPool::new(&wasm, 3).unwrap());
+        let inputs = |i: u32| {
+            vec![
+                ("a".to_string(), vec![BigInt::from(i + 2)]),
+                ("b".to_string(), vec![BigInt::from(i + 5)]),
+            ]
+        };
+
+        let handles = (0..12u32)
+            .map(|i| {
+                let pool = pool.clone();
+                std::thread::spawn(move || pool.calculate(inputs(i), true).unwrap())
+            })
+            .collect::<Vec<_>>();
+        let mut wtns = WitnessCalculator::from_file(&wasm_file).unwrap();
+        for (i, handle) in handles.into_iter().enumerate() {
+            let expected = wtns.calculate_witness(inputs(i as u32), true).unwrap();
+            assert_eq!(handle.join().unwrap(), expected);
+        }
+        assert_eq!(pool.idle.lock().unwrap().len(), 3);
+    }
+}
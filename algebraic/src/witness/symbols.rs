@@ -0,0 +1,133 @@
+//! The `.sym` file circom writes next to the r1cs, one `label,witness,component,name` line
+//! per signal, with witness `-1` for the signals the simplifier removed.
+use anyhow::{anyhow, bail, Result};
+use num_bigint::BigInt;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Clone, Debug, Default)]
+pub struct SymbolTable {
+    // full signal name, e.g. `main.out[1]`, to witness index
+    signals: HashMap<String, usize>,
+}
+
+impl SymbolTable {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let sym = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Open {}, {:?}", path.display(), e))?;
+        Self::parse(&sym)
+    }
+
+    pub fn parse(sym: &str) -> Result<Self> {
+        let mut signals = HashMap::new();
+        for (i, line) in sym.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields = line.splitn(4, ',').collect::<Vec<_>>();
+            if fields.len() != 4 {
+                bail!("invalid sym line {}: {}", i + 1, line);
+            }
+            let witness = fields[1]
+                .trim()
+                .parse::<i64>()
+                .map_err(|e| anyhow!("invalid sym line {}: {}, {:?}", i + 1, line, e))?;
+            if witness >= 0 {
+                signals.insert(fields[3].trim().to_string(), witness as usize);
+            }
+        }
+        Ok(SymbolTable { signals })
+    }
+
+    /// The witness index of `name`, which may omit the leading `main.`.
+    pub fn witness_index(&self, name: &str) -> Option<usize> {
+        self.signals
+            .get(name)
+            .or_else(|| self.signals.get(&format!("main.{}", name)))
+            .copied()
+    }
+
+    /// The full name of every signal kept in the witness, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.signals.keys().map(|name| name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.signals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signals.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OutputMismatch {
+    pub name: String,
+    pub expected: BigInt,
+    pub actual: BigInt,
+}
+
+/// Compare the named signals of `witness` against `expected`, returning the ones that differ
+/// sorted by name. Fails if a name isn't in the symbol table or out of the witness range.
+pub fn diff_public_outputs(
+    symbols: &SymbolTable,
+    witness: &[BigInt],
+    expected: &HashMap<String, BigInt>,
+) -> Result<Vec<OutputMismatch>> {
+    let mut mismatches = vec![];
+    for (name, value) in expected.iter() {
+        let idx = symbols
+            .witness_index(name)
+            .ok_or_else(|| anyhow!("signal {} not found in the symbol table", name))?;
+        let actual = witness.get(idx).ok_or_else(|| {
+            anyhow!(
+                "signal {} maps to witness {}, but the witness has {} elements",
+                name,
+                idx,
+                witness.len()
+            )
+        })?;
+        if actual != value {
+            mismatches.push(OutputMismatch {
+                name: name.clone(),
+                expected: value.clone(),
+                actual: actual.clone(),
+            });
+        }
+    }
+    mismatches.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_public_outputs() {
+        let symbols = SymbolTable::parse(
+            "1,1,0,main.c\n2,2,0,main.d\n3,3,0,main.a\n4,-1,0,main.tmp\n5,4,0,main.b\n",
+        )
+        .unwrap();
+        assert_eq!(symbols.len(), 4);
+        assert_eq!(symbols.witness_index("main.tmp"), None);
+
+        let witness = [1u32, 33, 14, 3, 11].map(BigInt::from).to_vec();
+        let mut expected = HashMap::new();
+        expected.insert("c".to_string(), BigInt::from(33u32));
+        expected.insert("main.d".to_string(), BigInt::from(15u32));
+        assert_eq!(
+            diff_public_outputs(&symbols, &witness, &expected).unwrap(),
+            vec![OutputMismatch {
+                name: "main.d".to_string(),
+                expected: BigInt::from(15u32),
+                actual: BigInt::from(14u32),
+            }]
+        );
+
+        expected.insert("main.tmp".to_string(), BigInt::from(0u32));
+        assert!(diff_public_outputs(&symbols, &witness, &expected).is_err());
+    }
+}
@@ -1,19 +1,22 @@
 // copied and modified by https://github.com/arkworks-rs/circom-compat/blob/master/src/witness/witness_calculator.rs
-use crate::bellman_ce::ScalarEngine;
-use crate::witness::{circom::Wasm, fnv, memory::SafeMemory};
-use anyhow::{bail, Result};
+use crate::bellman_ce::{worker::Worker, ScalarEngine};
+use crate::errors::EigenError;
+use crate::witness::{circom::Wasm, fnv, memory::SafeMemory, InputSignal, SymbolTable, Visibility};
+use crate::{Field, PrimeField, PrimeFieldRepr};
+use anyhow::{anyhow, bail, Result};
 use num::ToPrimitive;
 use num_bigint::BigInt;
 use num_bigint::Sign;
 use num_traits::{One, Zero};
 use serde_json::Value;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use wasmer::{imports, Function, Instance, Memory, MemoryType, Module, Store};
 
 use std::fs::OpenOptions;
-use std::io::{BufWriter, Write};
+use std::io::{BufReader, BufWriter, Read, Write};
 
-use byteorder::{LittleEndian, WriteBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
 pub struct WitnessCalculator {
     pub instance: Wasm,
@@ -21,8 +24,31 @@ pub struct WitnessCalculator {
     pub memory: SafeMemory,
     pub n64: u32,
     pub circom_version: u32,
+    // the wasm only knows the inputs by the fnv hash of their names
+    symbols: Option<SymbolTable>,
 }
 
+/// Linear memory given to the witness wasm, in 64KiB pages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WitnessCalculatorConfig {
+    pub initial_pages: u32,
+    /// `None` lets the memory grow up to the wasm32 limit
+    pub max_pages: Option<u32>,
+}
+
+impl Default for WitnessCalculatorConfig {
+    fn default() -> Self {
+        // 2000 pages, about 128MB
+        WitnessCalculatorConfig {
+            initial_pages: 2000,
+            max_pages: None,
+        }
+    }
+}
+
+/// How many wires `calculate_witness_with_progress` reads between two progress calls.
+pub const PROGRESS_INTERVAL: u32 = 1 << 16;
+
 fn from_array32(arr: Vec<u32>) -> BigInt {
     let mut res = BigInt::zero();
     let radix = BigInt::from(0x100000000u64);
@@ -32,6 +58,22 @@ fn from_array32(arr: Vec<u32>) -> BigInt {
     res
 }
 
+// `n32` words per element, least significant first, as read out of the instance
+#[cfg_attr(feature = "parallel-witness", allow(dead_code))]
+fn words_to_bigints(words: &[u32], n32: usize) -> Vec<BigInt> {
+    words.chunks(n32).map(words_to_bigint).collect()
+}
+
+#[cfg(feature = "parallel-witness")]
+fn words_to_bigints_parallel(words: &[u32], n32: usize) -> Vec<BigInt> {
+    use rayon::prelude::*;
+    words.par_chunks(n32).map(words_to_bigint).collect()
+}
+
+fn words_to_bigint(words: &[u32]) -> BigInt {
+    from_array32(words.iter().rev().copied().collect())
+}
+
 fn to_array32(s: &BigInt, size: usize) -> Vec<u32> {
     let mut res = vec![0; size];
     let mut rem = s.clone();
@@ -46,18 +88,120 @@ fn to_array32(s: &BigInt, size: usize) -> Vec<u32> {
     res
 }
 
+/// `s` as `size` 32-bit words, least significant word first, the layout written to the
+/// wasm shared memory
+pub(crate) fn to_words_le(s: &BigInt, size: usize) -> Vec<u32> {
+    let mut res = to_array32(s, size);
+    res.reverse();
+    res
+}
+
+/// An input value as the words written to the wasm shared memory, negative values
+/// wrapping around the modulus, as in circom
+pub(crate) fn input_to_words_le(value: &BigInt, prime: &BigInt, size: usize) -> Vec<u32> {
+    if value.sign() == Sign::Minus {
+        to_words_le(&((value % prime + prime) % prime), size)
+    } else {
+        to_words_le(value, size)
+    }
+}
+
+/// circom puts the constant signal `1` at index 0, anything else means the wasm or the
+/// runtime produced garbage
+fn check_witness_constant(witness: &[BigInt]) -> Result<()> {
+    match witness.first() {
+        Some(w) if w.is_one() => Ok(()),
+        Some(w) => bail!(EigenError::MalformedWitness(format!(
+            "witness[0] is {}, expected 1",
+            w
+        ))),
+        None => bail!(EigenError::MalformedWitness("empty witness".to_string())),
+    }
+}
+
+/// Check `witness_size` elements of `n32` words, as the instance reports them, fit the
+/// instance's memory, so a circuit with inconsistent sizes fails here rather than reading
+/// garbage or allocating without bound.
+fn check_witness_layout(
+    memory: &SafeMemory,
+    store: &Store,
+    witness_size: u32,
+    n32: u32,
+) -> std::result::Result<(), EigenError> {
+    if n32 == 0 {
+        return Err(EigenError::MalformedWitness(
+            "the circuit reports 0 words per field element".to_string(),
+        ));
+    }
+    memory.check_bounds(store, 0, witness_size as u64 * n32 as u64 * 4)
+}
+
 impl WitnessCalculator {
     pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        Self::from_file_with_config(path, &WitnessCalculatorConfig::default())
+    }
+
+    pub fn from_file_with_config(
+        path: impl AsRef<std::path::Path>,
+        config: &WitnessCalculatorConfig,
+    ) -> Result<Self> {
+        let path = path.as_ref();
         let mut store = Store::default();
-        let module = Module::from_file(&store, path)?;
-        let mut wtns = Self::from_module(&mut store, module)?;
+        let module = Module::from_file(&store, path)
+            .map_err(|e| EigenError::WasmLoad(format!("{}: {}", path.display(), e)))?;
+        let mut wtns = Self::from_module_with_memory(
+            &mut store,
+            module,
+            config.initial_pages,
+            config.max_pages,
+        )?;
+        wtns.store = store;
+        Ok(wtns)
+    }
+
+    /// Same as `from_file`, from the wasm already in memory, e.g. embedded in the binary
+    /// or from `dsl_compile::compile_to_buffers`.
+    pub fn from_bytes(wasm: &[u8]) -> Result<Self> {
+        let store = Store::default();
+        let module = Module::new(&store, wasm).map_err(|e| EigenError::WasmLoad(e.to_string()))?;
+        Self::from_store_and_module(store, &module)
+    }
+
+    /// Instantiate `module` in `store`, which then belongs to the calculator
+    pub(crate) fn from_store_and_module(mut store: Store, module: &Module) -> Result<Self> {
+        let mut wtns = Self::from_module(&mut store, module.clone())?;
         wtns.store = store;
         Ok(wtns)
     }
 
     pub fn from_module(store: &mut Store, module: Module) -> Result<Self> {
+        let config = WitnessCalculatorConfig::default();
+        Self::from_module_with_memory(store, module, config.initial_pages, config.max_pages)
+    }
+
+    /// `from_module` with `initial_pages` of memory, growable up to `max_pages`.
+    pub fn from_module_with_memory(
+        store: &mut Store,
+        module: Module,
+        initial_pages: u32,
+        max_pages: Option<u32>,
+    ) -> Result<Self> {
+        if initial_pages == 0 {
+            bail!(EigenError::WasmMemory(
+                "initial_pages must be nonzero".to_string()
+            ));
+        }
+        if let Some(max) = max_pages.filter(|max| *max < initial_pages) {
+            bail!(EigenError::WasmMemory(format!(
+                "max_pages {} is below initial_pages {}",
+                max, initial_pages
+            )));
+        }
         // Set up the memory
-        let memory = Memory::new(store, MemoryType::new(2000, None, false))?;
+        let memory =
+            Memory::new(store, MemoryType::new(initial_pages, max_pages, false)).map_err(|e| {
+                EigenError::WasmMemory(format!("allocate {} pages: {}", initial_pages, e))
+            })?;
         let import_object = imports! {
             "env" => {
                 "memory" => memory.clone(),
@@ -88,6 +232,7 @@ impl WitnessCalculator {
 
             let n32 = instance.get_field_num_len32(store)?;
             let mut safe_memory = SafeMemory::new(memory, n32 as usize, BigInt::zero());
+            check_witness_layout(&safe_memory, store, 1, n32)?;
             instance.get_raw_prime(store)?;
             let mut arr = vec![0; n32 as usize];
             for i in 0..n32 {
@@ -105,31 +250,229 @@ impl WitnessCalculator {
                 memory: safe_memory,
                 n64,
                 circom_version: version,
+                symbols: None,
             })
         }
 
         new_circom(store, instance, memory)
     }
 
+    /// Give the circuit's `.sym` table, the names `input_signals` resolves against.
+    pub fn set_symbols(&mut self, symbols: SymbolTable) {
+        self.symbols = Some(symbols);
+    }
+
+    /// The inputs of the main component and their number of elements, sorted by name.
+    ///
+    /// The wasm only holds the hashes of the input names, so they are looked up from the
+    /// table given to `set_symbols`, failing with `EigenError::Unsupported` without one. This
+    /// only reads the circuit's input table, the instance needn't be `init`ed and nothing is
+    /// written.
+    pub fn input_signals(&mut self) -> Result<Vec<(String, usize)>> {
+        let symbols = match &self.symbols {
+            Some(symbols) => symbols,
+            None => bail!(EigenError::Unsupported(
+                "the wasm carries no signal names, set the symbol table first".to_string()
+            )),
+        };
+        // `main.in[2][1]` is an element of the input `in`, `main.sub.x` a subcomponent's
+        let mut candidates = symbols
+            .names()
+            .filter_map(|name| name.strip_prefix("main."))
+            .map(|name| name.split('[').next().unwrap_or(name))
+            .filter(|name| !name.contains('.'))
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let mut inputs = vec![];
+        for name in candidates {
+            let (msb, lsb) = fnv(&name);
+            if let Some(size) = self
+                .instance
+                .get_input_signal_size(&mut self.store, msb, lsb)
+            {
+                inputs.push((name, size as usize));
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// `input_signals` with the visibility of each input, for `Inputs::build`.
+    ///
+    /// `num_inputs` is the circuit's r1cs `num_inputs`, the constant one and the public
+    /// outputs and inputs. Those take the first witness indices, so an input is public if its
+    /// first element's index in the symbol table is below it.
+    pub fn circuit_inputs(&mut self, num_inputs: usize) -> Result<Vec<InputSignal>> {
+        let inputs = self.input_signals()?;
+        let symbols = self
+            .symbols
+            .as_ref()
+            .ok_or_else(|| anyhow!("no symbol table"))?;
+        inputs
+            .into_iter()
+            .map(|(name, size)| {
+                let prefix = format!("main.{}", name);
+                let index = symbols
+                    .names()
+                    .filter(|n| {
+                        n.strip_prefix(prefix.as_str())
+                            .map_or(false, |rest| rest.is_empty() || rest.starts_with('['))
+                    })
+                    .filter_map(|n| symbols.witness_index(n))
+                    .min()
+                    .ok_or_else(|| anyhow!("input {} is not in the witness", name))?;
+                let visibility = if index < num_inputs {
+                    Visibility::Public
+                } else {
+                    Visibility::Private
+                };
+                Ok(InputSignal {
+                    name,
+                    size,
+                    visibility,
+                })
+            })
+            .collect()
+    }
+
     pub fn calculate_witness<I: IntoIterator<Item = (String, Vec<BigInt>)>>(
         &mut self,
         // store: &mut Store,
         inputs: I,
         sanity_check: bool,
     ) -> Result<Vec<BigInt>> {
-        self.instance.init(&mut self.store, sanity_check)?;
-        let wtns_u32 = self.calculate_witness_circom(inputs, sanity_check)?;
-        let n32 = self.instance.get_field_num_len32(&mut self.store)?;
+        self.calculate_witness_with_progress(inputs, sanity_check, |_, _| {})
+    }
 
-        let mut wo = Vec::new();
-        let witness_size = self.instance.get_witness_size(&mut self.store)?;
-        for i in 0..witness_size {
-            let mut arr = vec![0u32; n32 as usize];
-            for j in 0..n32 {
-                arr[(n32 - 1 - j) as usize] = wtns_u32[(i * n32 + j) as usize];
+    /// Same as `calculate_witness`, calling `progress(current_wire, total)` every
+    /// `PROGRESS_INTERVAL` wires while the witness is read out of the instance, and once more
+    /// with `current_wire == total` at the end.
+    pub fn calculate_witness_with_progress<I, F>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+        progress: F,
+    ) -> Result<Vec<BigInt>>
+    where
+        I: IntoIterator<Item = (String, Vec<BigInt>)>,
+        F: Fn(u32, u32),
+    {
+        let wtns_u32 = self.calculate_witness_circom(inputs, sanity_check, &progress)?;
+        let witness = self.witness_from_u32(&wtns_u32)?;
+        if sanity_check {
+            check_witness_constant(&witness)?;
+        }
+        Ok(witness)
+    }
+
+    /// `calculate_witness` for each of `input_sets` in turn, on the same instance. Every
+    /// calculation re-initializes the instance and rewrites the shared memory, so no state
+    /// leaks from one set to the next. The calculator and its `SafeMemory` are used through
+    /// `&mut self`, one calculation at a time; to compute witnesses concurrently use a
+    /// `WitnessCalculatorPool`.
+    pub fn calculate_witnesses<I, S>(
+        &mut self,
+        input_sets: S,
+        sanity_check: bool,
+    ) -> Result<Vec<Vec<BigInt>>>
+    where
+        I: IntoIterator<Item = (String, Vec<BigInt>)>,
+        S: IntoIterator<Item = I>,
+    {
+        input_sets
+            .into_iter()
+            .map(|inputs| self.calculate_witness(inputs, sanity_check))
+            .collect()
+    }
+
+    /// Same as `calculate_witness`, with every input already encoded by `prepare_inputs_u32`
+    pub fn calculate_witness_u32<I: IntoIterator<Item = (String, Vec<u32>)>>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<BigInt>> {
+        let wtns_u32 = self.calculate_witness_circom_u32(inputs, sanity_check, &|_, _| {})?;
+        let witness = self.witness_from_u32(&wtns_u32)?;
+        if sanity_check {
+            check_witness_constant(&witness)?;
+        }
+        Ok(witness)
+    }
+
+    /// Same as `calculate_witness`, converted to `E::Fr`: the words of every element go
+    /// straight into the field's repr, splitting the witness across the worker threads,
+    /// without the `BigInt` and decimal string round trip.
+    pub fn calculate_witness_fr_parallel<E, I>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+    ) -> Result<Vec<E::Fr>>
+    where
+        E: ScalarEngine,
+        I: IntoIterator<Item = (String, Vec<BigInt>)>,
+    {
+        let wtns_u32 = self.calculate_witness_circom(inputs, sanity_check, &|_, _| {})?;
+        let n32 = self.instance.get_field_num_len32(&mut self.store)? as usize;
+        let n64 = <E::Fr as PrimeField>::Repr::default().as_ref().len();
+        if n32 != 2 * n64 {
+            bail!(
+                "the circuit's field has {} words, {} needs {}",
+                n32,
+                std::any::type_name::<E::Fr>(),
+                2 * n64
+            );
+        }
+
+        let mut witness = vec![E::Fr::zero(); wtns_u32.len() / n32];
+        let non_canonical = AtomicBool::new(false);
+        let worker = Worker::new();
+        worker.scope(witness.len(), |scope, chunk| {
+            for (out, words) in witness.chunks_mut(chunk).zip(wtns_u32.chunks(chunk * n32)) {
+                let non_canonical = &non_canonical;
+                scope.spawn(move |_| {
+                    for (el, words) in out.iter_mut().zip(words.chunks(n32)) {
+                        let mut repr = <E::Fr as PrimeField>::Repr::default();
+                        for (limb, pair) in repr.as_mut().iter_mut().zip(words.chunks(2)) {
+                            *limb = pair[0] as u64 | (pair[1] as u64) << 32;
+                        }
+                        match E::Fr::from_repr(repr) {
+                            Ok(v) => *el = v,
+                            Err(_) => non_canonical.store(true, Ordering::Relaxed),
+                        }
+                    }
+                });
             }
-            wo.push(from_array32(arr));
+        });
+        if non_canonical.load(Ordering::Relaxed) {
+            bail!(EigenError::MalformedWitness(
+                "witness element not below the field modulus".to_string()
+            ));
+        }
+        if sanity_check && witness.first() != Some(&E::Fr::one()) {
+            bail!(EigenError::MalformedWitness(
+                "witness[0] isn't 1".to_string()
+            ));
         }
+        Ok(witness)
+    }
+
+    fn witness_from_u32(&mut self, wtns_u32: &[u32]) -> Result<Vec<BigInt>> {
+        let n32 = self.instance.get_field_num_len32(&mut self.store)? as usize;
+        let witness_size = self.instance.get_witness_size(&mut self.store)? as usize;
+        let words = wtns_u32.get(..witness_size * n32).ok_or_else(|| {
+            anyhow!(
+                "expect {} witness words, got {}",
+                witness_size * n32,
+                wtns_u32.len()
+            )
+        })?;
+
+        #[cfg(feature = "parallel-witness")]
+        let wo = words_to_bigints_parallel(words, n32);
+        #[cfg(not(feature = "parallel-witness"))]
+        let wo = words_to_bigints(words, n32);
         Ok(wo)
     }
 
@@ -138,8 +481,7 @@ impl WitnessCalculator {
         inputs: I,
         sanity_check: bool,
     ) -> Result<Vec<u32>> {
-        self.instance.init(&mut self.store, sanity_check)?;
-        self.calculate_witness_circom(inputs, sanity_check)
+        self.calculate_witness_circom(inputs, sanity_check, &|_, _| {})
     }
 
     // Circom 2 feature flag with version 2
@@ -147,23 +489,60 @@ impl WitnessCalculator {
         &mut self,
         inputs: I,
         sanity_check: bool,
+        progress: &dyn Fn(u32, u32),
+    ) -> Result<Vec<u32>> {
+        let n32 = self.instance.get_field_num_len32(&mut self.store)?;
+        let prime = self.memory.prime.clone();
+        let inputs = inputs.into_iter().map(|(name, values)| {
+            let words = values
+                .iter()
+                .flat_map(|value| input_to_words_le(value, &prime, n32 as usize))
+                .collect::<Vec<_>>();
+            (name, words)
+        });
+        self.calculate_witness_circom_u32(inputs, sanity_check, progress)
+    }
+
+    // the only place the instance is initialized, once per witness, every calculation goes
+    // through here
+    fn calculate_witness_circom_u32<I: IntoIterator<Item = (String, Vec<u32>)>>(
+        &mut self,
+        inputs: I,
+        sanity_check: bool,
+        progress: &dyn Fn(u32, u32),
     ) -> Result<Vec<u32>> {
         self.instance.init(&mut self.store, sanity_check)?;
 
         let n32 = self.instance.get_field_num_len32(&mut self.store)?;
 
         // allocate the inputs
-        for (name, values) in inputs.into_iter() {
+        for (name, words) in inputs.into_iter() {
             let (msb, lsb) = fnv(&name);
+            if words.len() % n32 as usize != 0 {
+                bail!(
+                    "input {} has {} words, not a multiple of {}",
+                    name,
+                    words.len(),
+                    n32
+                );
+            }
+            let got = words.len() / n32 as usize;
+            let expected = self
+                .instance
+                .get_input_signal_size(&mut self.store, msb, lsb)
+                .unwrap_or(0) as usize;
+            if expected != got {
+                bail!(EigenError::InputSignal {
+                    name,
+                    expected,
+                    got
+                });
+            }
 
-            for (i, value) in values.into_iter().enumerate() {
-                let f_arr = to_array32(&value, n32 as usize);
-                for j in 0..n32 {
-                    self.instance.write_shared_rw_memory(
-                        &mut self.store,
-                        j,
-                        f_arr[(n32 as usize) - 1 - (j as usize)],
-                    )?;
+            for (i, value) in words.chunks(n32 as usize).enumerate() {
+                for (j, word) in value.iter().enumerate() {
+                    self.instance
+                        .write_shared_rw_memory(&mut self.store, j as u32, *word)?;
                 }
                 self.instance
                     .set_input_signal(&mut self.store, msb, lsb, i as u32)?;
@@ -173,12 +552,17 @@ impl WitnessCalculator {
         let mut w = Vec::new();
 
         let witness_size = self.instance.get_witness_size(&mut self.store)?;
+        check_witness_layout(&self.memory, &self.store, witness_size, n32)?;
         for i in 0..witness_size {
+            if i % PROGRESS_INTERVAL == 0 {
+                progress(i, witness_size);
+            }
             self.instance.get_witness(&mut self.store, i)?;
             for j in 0..n32 {
                 w.push(self.instance.read_shared_rw_memory(&mut self.store, j)?);
             }
         }
+        progress(witness_size, witness_size);
 
         Ok(w)
     }
@@ -252,51 +636,137 @@ impl WitnessCalculator {
         }
         Ok(())
     }
+
+    pub fn read_witness_from_bin_file(&self, filename: &str) -> Result<Vec<BigInt>> {
+        let reader = OpenOptions::new()
+            .read(true)
+            .open(filename)
+            .map_err(|e| anyhow!("Open {}, {:?}", filename, e))?;
+        self.read_witness_from_bin_reader(BufReader::new(reader))
+    }
+
+    /// Inverse of `save_witness_from_bin_writer`, the prime in the header must be this
+    /// circuit's.
+    pub fn read_witness_from_bin_reader<R: Read>(&self, mut reader: R) -> Result<Vec<BigInt>> {
+        let stats = crate::reader::read_wtns_header(&mut reader)?;
+        let prime = BigInt::from_bytes_le(Sign::Plus, &stats.prime);
+        if prime != self.memory.prime {
+            bail!(
+                "Invalid prime: {}, the circuit's is {}",
+                prime,
+                self.memory.prime
+            );
+        }
+        Ok(crate::reader::read_wtns_data(&mut reader, &stats)?
+            .chunks(stats.field_size as usize)
+            .map(|bytes| BigInt::from_bytes_le(Sign::Plus, bytes))
+            .collect())
+    }
 }
 
-#[allow(dead_code)]
-pub fn value_to_bigint(v: Value) -> BigInt {
+/// The `E::Fr` of a witness value, negative values wrapping around `E::Fr`'s modulus.
+pub fn calculate_witness_element<E: ScalarEngine>(w: &BigInt) -> Result<E::Fr> {
+    let modulus = BigInt::from_str(&crate::utils::repr_to_big(E::Fr::char()))?;
+    let w = ((w % &modulus) + &modulus) % &modulus;
+    E::Fr::from_str(&w.to_string()).ok_or_else(|| anyhow!("invalid witness element {}", w))
+}
+
+/// Parse a scalar input value the way `try_flat_array` parses the elements of an array.
+/// Numbers may be negative or beyond the `u64` range, but must be integers.
+pub fn value_to_bigint(v: Value) -> std::result::Result<BigInt, EigenError> {
+    scalar_to_bigint(&v).map_err(|reason| EigenError::InputValue {
+        path: String::new(),
+        reason,
+    })
+}
+
+fn scalar_to_bigint(v: &Value) -> std::result::Result<BigInt, String> {
     match v {
-        Value::String(inner) => BigInt::from_str(&inner).unwrap(),
-        Value::Number(inner) => BigInt::from(
-            inner
-                .as_u64()
-                .unwrap_or_else(|| panic!("{} not a u32", inner)),
-        ),
-        _ => panic!("unsupported type {:?}", v),
+        Value::String(inner) => {
+            BigInt::from_str(inner).map_err(|e| format!("{:?}, {:?}", inner, e))
+        }
+        // with `arbitrary_precision` this is the number as written, so nothing is lost to
+        // f64 or u64 on the way
+        Value::Number(inner) => {
+            let digits = inner.to_string();
+            if digits.contains(['.', 'e', 'E']) {
+                return Err(format!("{} is not an integer", digits));
+            }
+            BigInt::from_str(&digits).map_err(|e| format!("{}, {:?}", digits, e))
+        }
+        Value::Bool(inner) => Ok(if *inner {
+            BigInt::one()
+        } else {
+            BigInt::zero()
+        }),
+        Value::Null => Err("null is not a valid input value".to_string()),
+        _ => Err(format!("expect a scalar, got {}", v)),
     }
 }
 
+#[deprecated(since = "0.0.2", note = "please use `try_flat_array` instead")]
 pub fn flat_array(v: &[Value]) -> Vec<BigInt> {
+    try_flat_array(v).unwrap_or_else(|e| panic!("{}", e))
+}
+
+/// Flatten `v` into its field values, arrays in order and objects in their declared field
+/// order. Errors carry the JSON pointer of the offending value, `/1/x` being the field `x`
+/// of `v[1]`.
+pub fn try_flat_array(v: &[Value]) -> std::result::Result<Vec<BigInt>, EigenError> {
     let mut result = Vec::new();
-    fn fill_array(out: &mut Vec<BigInt>, value: &Value) {
-        match value {
-            Value::Array(inner) => {
-                for v2 in inner.iter() {
-                    fill_array(out, v2);
-                }
-            }
-            Value::Bool(inner) => {
-                if *inner {
-                    out.push(BigInt::one());
-                } else {
-                    out.push(BigInt::zero());
-                }
-            }
-            Value::String(inner) => {
-                out.push(BigInt::from_str(inner).unwrap());
+    for (i, v2) in v.iter().enumerate() {
+        fill_array(&mut result, v2, &mut format!("/{}", i))?;
+    }
+    Ok(result)
+}
+
+/// `try_flat_array` of the value of the input `name`, the pointers rooted at `/name`.
+pub(crate) fn flatten_input(
+    name: &str,
+    value: &Value,
+) -> std::result::Result<Vec<BigInt>, EigenError> {
+    let mut result = Vec::new();
+    fill_array(
+        &mut result,
+        value,
+        &mut format!("/{}", escape_pointer(name)),
+    )?;
+    Ok(result)
+}
+
+pub(crate) fn escape_pointer(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn fill_array(
+    out: &mut Vec<BigInt>,
+    value: &Value,
+    path: &mut String,
+) -> std::result::Result<(), EigenError> {
+    let invalid = |path: &str, reason: String| EigenError::InputValue {
+        path: path.to_string(),
+        reason,
+    };
+    match value {
+        Value::Array(inner) => {
+            for (i, v2) in inner.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("/{}", i));
+                fill_array(out, v2, path)?;
+                path.truncate(len);
             }
-            Value::Number(inner) => {
-                out.push(BigInt::from_str(&inner.to_string()).unwrap());
+        }
+        Value::Object(inner) => {
+            for (k, v2) in inner.iter() {
+                let len = path.len();
+                path.push_str(&format!("/{}", escape_pointer(k)));
+                fill_array(out, v2, path)?;
+                path.truncate(len);
             }
-            _ => panic!(),
         }
+        _ => out.push(scalar_to_bigint(value).map_err(|reason| invalid(path, reason))?),
     }
-
-    for v2 in v.iter() {
-        fill_array(&mut result, v2);
-    }
-    result
+    Ok(())
 }
 
 // callback hooks for debugging
@@ -324,8 +794,19 @@ mod runtime {
 
     // Circom 2.0
     pub fn exception_handler(store: &mut Store) -> Function {
-        #[allow(unused)]
-        fn func(a: i32) {}
+        // the codes of circom_runtime's witness_calculator.js
+        fn func(code: i32) -> std::result::Result<(), wasmer::RuntimeError> {
+            let msg = match code {
+                1 => "Signal not found",
+                2 => "Too many signals set",
+                3 => "Signal already set",
+                4 => "Assert Failed",
+                5 => "Not enough memory",
+                6 => "Input signal array access exceeds the size",
+                _ => "Unknown error",
+            };
+            Err(wasmer::RuntimeError::new(msg))
+        }
         Function::new_typed(store, func)
     }
 
@@ -382,6 +863,269 @@ mod tests {
         path.to_string_lossy().to_string()
     }
 
+    #[test]
+    fn test_memory_bounds() {
+        let path = root_path("test-vectors/mycircuit.wasm");
+        let mut wtns = WitnessCalculator::from_file(path).unwrap();
+        let n32 = wtns.instance.get_field_num_len32(&mut wtns.store).unwrap();
+        let witness_size = wtns.instance.get_witness_size(&mut wtns.store).unwrap();
+        check_witness_layout(&wtns.memory, &wtns.store, witness_size, n32).unwrap();
+
+        // a circuit claiming more witness than its memory holds
+        let oversized = u32::MAX;
+        match check_witness_layout(&wtns.memory, &wtns.store, oversized, n32) {
+            Err(EigenError::MemoryOutOfBounds { offset, len }) => {
+                assert_eq!((offset, len), (0, oversized as u64 * n32 as u64 * 4))
+            }
+            r => panic!("unexpected {:?}", r),
+        }
+        assert!(matches!(
+            check_witness_layout(&wtns.memory, &wtns.store, witness_size, 0),
+            Err(EigenError::MalformedWitness(_))
+        ));
+
+        let size = wtns.memory.size(&wtns.store);
+        wtns.memory
+            .write_u32(&wtns.store, size - 4, 0xdeadbeef)
+            .unwrap();
+        assert_eq!(
+            wtns.memory.read_u32(&wtns.store, size - 4).unwrap(),
+            0xdeadbeef
+        );
+        for offset in [size - 3, size, u64::MAX] {
+            assert!(matches!(
+                wtns.memory.read_u32(&wtns.store, offset),
+                Err(EigenError::MemoryOutOfBounds { .. })
+            ));
+        }
+        assert!(wtns.memory.write_u32(&wtns.store, size, 0).is_err());
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let wasm = std::fs::read(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let mut wtns = WitnessCalculator::from_bytes(&wasm).unwrap();
+        let inputs = vec![
+            ("a".to_string(), vec![BigInt::from(3u32)]),
+            ("b".to_string(), vec![BigInt::from(11u32)]),
+        ];
+        assert_eq!(
+            wtns.calculate_witness(inputs, false).unwrap(),
+            [1u32, 33, 3, 11].map(BigInt::from).to_vec()
+        );
+
+        let err = WitnessCalculator::from_bytes(&wasm[..wasm.len() / 2])
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.downcast_ref::<EigenError>(),
+            Some(EigenError::WasmLoad(_))
+        ));
+    }
+
+    #[test]
+    fn test_memory_config() {
+        let path = root_path("test-vectors/mycircuit.wasm");
+        let config = WitnessCalculatorConfig {
+            initial_pages: 16,
+            max_pages: Some(64),
+        };
+        let mut wtns = WitnessCalculator::from_file_with_config(&path, &config).unwrap();
+        let inputs = vec![
+            ("a".to_string(), vec![BigInt::from(3u32)]),
+            ("b".to_string(), vec![BigInt::from(11u32)]),
+        ];
+        assert_eq!(
+            wtns.calculate_witness(inputs, false).unwrap(),
+            [1u32, 33, 3, 11].map(BigInt::from).to_vec()
+        );
+
+        for (initial_pages, max_pages) in [(0, None), (16, Some(8))] {
+            let config = WitnessCalculatorConfig {
+                initial_pages,
+                max_pages,
+            };
+            let err = WitnessCalculator::from_file_with_config(&path, &config)
+                .err()
+                .unwrap();
+            assert!(matches!(
+                err.downcast_ref::<EigenError>(),
+                Some(EigenError::WasmMemory(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_calculate_witnesses() {
+        let path = root_path("test-vectors/mycircuit.wasm");
+        let input_sets = [(3u32, 11u32), (5, 7)]
+            .iter()
+            .map(|(a, b)| {
+                vec![
+                    ("a".to_string(), vec![BigInt::from(*a)]),
+                    ("b".to_string(), vec![BigInt::from(*b)]),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let mut wtns = WitnessCalculator::from_file(&path).unwrap();
+        let batch = wtns.calculate_witnesses(input_sets.clone(), true).unwrap();
+        assert_eq!(batch.len(), 2);
+        for (inputs, witness) in input_sets.into_iter().zip(batch) {
+            let mut single = WitnessCalculator::from_file(&path).unwrap();
+            assert_eq!(single.calculate_witness(inputs, true).unwrap(), witness);
+        }
+    }
+
+    #[test]
+    fn test_read_witness_from_bin() {
+        use crate::bellman_ce::pairing::bn256::Bn256;
+        let mut wtns =
+            WitnessCalculator::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let inputs = vec![
+            ("a".to_string(), vec![BigInt::from(3u32)]),
+            ("b".to_string(), vec![BigInt::from(11u32)]),
+        ];
+        let w = wtns.calculate_witness_bin(inputs, true).unwrap();
+        let mut bin = vec![];
+        wtns.save_witness_from_bin_writer::<Bn256, _>(&mut bin, &w)
+            .unwrap();
+        assert_eq!(
+            wtns.read_witness_from_bin_reader(&bin[..]).unwrap(),
+            [1u32, 33, 3, 11].map(BigInt::from).to_vec()
+        );
+
+        let mut bad_magic = bin.clone();
+        bad_magic[0] = b'x';
+        assert!(wtns.read_witness_from_bin_reader(&bad_magic[..]).is_err());
+        let mut bad_version = bin.clone();
+        bad_version[4] = 7;
+        assert!(wtns.read_witness_from_bin_reader(&bad_version[..]).is_err());
+        // the header section size must be 4 + field size + 4
+        let mut bad_header_size = bin.clone();
+        bad_header_size[16] ^= 1;
+        assert!(wtns
+            .read_witness_from_bin_reader(&bad_header_size[..])
+            .is_err());
+        // a witness count far beyond the data, with a matching section size
+        let mut oversized = bin.clone();
+        oversized[60..64].copy_from_slice(&u32::MAX.to_le_bytes());
+        oversized[68..76].copy_from_slice(&(u32::MAX as u64 * 32).to_le_bytes());
+        assert!(wtns.read_witness_from_bin_reader(&oversized[..]).is_err());
+        // the prime starts after magic, version, section count, section header and field size
+        let mut bad_prime = bin;
+        bad_prime[28] ^= 1;
+        assert!(wtns.read_witness_from_bin_reader(&bad_prime[..]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel-witness")]
+    fn test_words_to_bigints_parallel() {
+        let n32 = 8;
+        let words = (0..5000 * n32 as u32)
+            .map(|i| i.wrapping_mul(0x9e3779b9))
+            .collect::<Vec<_>>();
+        let serial = words_to_bigints(&words, n32);
+        assert_eq!(serial.len(), 5000);
+        assert_eq!(words_to_bigints_parallel(&words, n32), serial);
+    }
+
+    #[test]
+    fn test_input_signal_validation() {
+        let mut wtns =
+            WitnessCalculator::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let cases = [
+            (
+                vec![
+                    ("a".to_string(), vec![BigInt::from(3u32)]),
+                    ("c".to_string(), vec![BigInt::from(11u32)]),
+                ],
+                ("c", 0, 1),
+            ),
+            (
+                vec![
+                    ("a".to_string(), vec![BigInt::from(3u32)]),
+                    (
+                        "b".to_string(),
+                        vec![BigInt::from(11u32), BigInt::from(1u32)],
+                    ),
+                ],
+                ("b", 1, 2),
+            ),
+        ];
+        for (inputs, (bad_name, bad_expected, bad_got)) in cases {
+            let err = wtns.calculate_witness(inputs, true).err().unwrap();
+            match err.downcast_ref::<EigenError>() {
+                Some(EigenError::InputSignal {
+                    name,
+                    expected,
+                    got,
+                }) => {
+                    assert_eq!(name, bad_name);
+                    assert_eq!((*expected, *got), (bad_expected, bad_got));
+                }
+                _ => panic!("unexpected error {:?}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_input_signals() {
+        let mut wtns =
+            WitnessCalculator::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let err = wtns.input_signals().err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<EigenError>(),
+            Some(EigenError::Unsupported(_))
+        ));
+
+        wtns.set_symbols(SymbolTable::parse("1,1,0,main.c\n2,2,0,main.a\n3,3,0,main.b\n").unwrap());
+        assert_eq!(
+            wtns.input_signals().unwrap(),
+            vec![("a".to_string(), 1), ("b".to_string(), 1)]
+        );
+
+        // `c` is the only public signal, say `a` were a public input
+        let private = wtns.circuit_inputs(2).unwrap();
+        assert!(private.iter().all(|s| s.visibility == Visibility::Private));
+        let public_a = wtns.circuit_inputs(3).unwrap();
+        assert_eq!(
+            public_a
+                .iter()
+                .map(|s| (s.name.as_str(), s.size, s.visibility))
+                .collect::<Vec<_>>(),
+            vec![("a", 1, Visibility::Public), ("b", 1, Visibility::Private)]
+        );
+
+        let inputs = crate::witness::Inputs::new()
+            .private("a", vec![BigInt::from(3u32)])
+            .private("b", vec![BigInt::from(11u32)])
+            .build(&private)
+            .unwrap();
+        assert_eq!(
+            wtns.calculate_witness(inputs, true).unwrap()[1],
+            BigInt::from(33u32)
+        );
+    }
+
+    #[test]
+    fn test_from_file_bad_wasm() {
+        let wasm = std::fs::read(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("eigen_truncated_{}.wasm", std::process::id()));
+        std::fs::write(&path, &wasm[..wasm.len() / 2]).unwrap();
+        let truncated = WitnessCalculator::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        for res in [truncated, WitnessCalculator::from_file(&path)] {
+            let err = res.err().unwrap();
+            assert!(matches!(
+                err.downcast_ref::<EigenError>(),
+                Some(EigenError::WasmLoad(_))
+            ));
+        }
+    }
+
     #[test]
     fn multiplier_1() {
         let inputs = HashMap::from([("a".to_string(), json!(3)), ("b".to_string(), json!(11))]);
@@ -444,6 +1188,240 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_calculate_witness_u32() {
+        let mut wtns =
+            WitnessCalculator::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let n32 = wtns.instance.get_field_num_len32(&mut wtns.store).unwrap();
+        let inputs_json = r#"{"a": "21888242871839275222246405745257275088548364400416034343698204186575796149939", "b": 11}"#;
+
+        let prime = wtns.memory.prime.clone();
+        let prepared = crate::witness::prepare_inputs_u32(inputs_json, &prime, n32).unwrap();
+        assert_eq!(
+            prepared.iter().map(|(_, w)| w.len()).sum::<usize>(),
+            2 * n32 as usize
+        );
+        let w = wtns.calculate_witness_u32(prepared, false).unwrap();
+
+        let inputs: HashMap<String, Value> = serde_json::from_str(inputs_json).unwrap();
+        let inputs = inputs
+            .into_iter()
+            .map(|(k, v)| (k, try_flat_array(&[v]).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(w, wtns.calculate_witness(inputs, false).unwrap());
+
+        let too_big = format!(r#"{{"a": "{}"}}"#, BigInt::from(1u32) << (32 * n32));
+        assert!(crate::witness::prepare_inputs_u32(&too_big, &prime, n32).is_err());
+
+        let negative_json = r#"{"a": -5, "b": 11}"#;
+        let prepared = crate::witness::prepare_inputs_u32(negative_json, &prime, n32).unwrap();
+        let w = wtns.calculate_witness_u32(prepared, false).unwrap();
+        let inputs = vec![
+            ("a".to_string(), vec![BigInt::from(-5)]),
+            ("b".to_string(), vec![BigInt::from(11u32)]),
+        ];
+        assert_eq!(w, wtns.calculate_witness(inputs, false).unwrap());
+    }
+
+    #[test]
+    fn test_calculate_witness_fr_parallel() {
+        use crate::bellman_ce::pairing::bn256::{Bn256, Fr};
+        let mut wtns =
+            WitnessCalculator::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let inputs = vec![
+            ("a".to_string(), vec![BigInt::from(3u32)]),
+            ("b".to_string(), vec![BigInt::from(11u32)]),
+        ];
+
+        let expected = wtns
+            .calculate_witness(inputs.clone(), true)
+            .unwrap()
+            .iter()
+            .map(|w| Fr::from_str(&w.to_string()).unwrap())
+            .collect::<Vec<_>>();
+        let w = wtns
+            .calculate_witness_fr_parallel::<Bn256, _>(inputs, true)
+            .unwrap();
+        assert_eq!(w, expected);
+    }
+
+    #[test]
+    fn test_calculate_witness_with_progress() {
+        let mut wtns =
+            WitnessCalculator::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let inputs = vec![
+            ("a".to_string(), vec![BigInt::from(3u32)]),
+            ("b".to_string(), vec![BigInt::from(11u32)]),
+        ];
+        let calls = std::cell::RefCell::new(vec![]);
+        let w = wtns
+            .calculate_witness_with_progress(inputs.clone(), true, |current, total| {
+                calls.borrow_mut().push((current, total))
+            })
+            .unwrap();
+        assert_eq!(w, wtns.calculate_witness(inputs, true).unwrap());
+
+        let calls = calls.into_inner();
+        let total = w.len() as u32;
+        assert_eq!(calls.first(), Some(&(0, total)));
+        assert_eq!(calls.last(), Some(&(total, total)));
+    }
+
+    #[test]
+    fn test_calculate_witness_element() {
+        use crate::bellman_ce::pairing::{bls12_381::Bls12, bn256::Bn256};
+        use crate::bellman_ce::ScalarEngine;
+        fn check<E: ScalarEngine>() {
+            let mut expected = E::Fr::from_str("5").unwrap();
+            expected.negate();
+            assert_eq!(
+                calculate_witness_element::<E>(&BigInt::from(-5)).unwrap(),
+                expected
+            );
+            assert_eq!(
+                calculate_witness_element::<E>(&BigInt::from(5)).unwrap(),
+                E::Fr::from_str("5").unwrap()
+            );
+            assert_eq!(
+                calculate_witness_element::<E>(&BigInt::zero()).unwrap(),
+                E::Fr::zero()
+            );
+        }
+        check::<Bls12>();
+        check::<Bn256>();
+    }
+
+    #[test]
+    fn test_try_flat_array() {
+        let value: Value =
+            serde_json::from_str(r#"[{"y": [3, "4"], "x": true}, [{"z": 5}, 6]]"#).unwrap();
+        assert_eq!(
+            try_flat_array(std::slice::from_ref(&value)).unwrap(),
+            [3u32, 4, 1, 5, 6].map(BigInt::from).to_vec()
+        );
+
+        let value: Value = serde_json::from_str(r#"{"a/b": [1, {"c": null}]}"#).unwrap();
+        match flatten_input("in", &value) {
+            Err(EigenError::InputValue { path, .. }) => assert_eq!(path, "/in/a~1b/1/c"),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_value_to_bigint() {
+        let inputs: HashMap<String, Value> = serde_json::from_str(
+            r#"{"flag": true, "off": false, "n": 7, "s": "-3", "none": null}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value_to_bigint(inputs["flag"].clone()).unwrap(),
+            BigInt::one()
+        );
+        assert_eq!(
+            value_to_bigint(inputs["off"].clone()).unwrap(),
+            BigInt::zero()
+        );
+        assert_eq!(
+            value_to_bigint(inputs["n"].clone()).unwrap(),
+            try_flat_array(&[inputs["n"].clone()]).unwrap()[0]
+        );
+        assert_eq!(
+            value_to_bigint(inputs["s"].clone()).unwrap(),
+            BigInt::from(-3)
+        );
+        let err = value_to_bigint(inputs["none"].clone()).unwrap_err();
+        assert!(err.to_string().contains("null"), "{}", err);
+
+        let big = "21888242871839275222246405745257275088548364400416034343698204186575808495616";
+        let numbers: Vec<Value> =
+            serde_json::from_str(&format!("[{}, -5, 1.5, 2e3]", big)).unwrap();
+        assert_eq!(
+            value_to_bigint(numbers[0].clone()).unwrap(),
+            BigInt::from_str(big).unwrap()
+        );
+        assert_eq!(
+            value_to_bigint(numbers[1].clone()).unwrap(),
+            BigInt::from(-5)
+        );
+        assert!(value_to_bigint(numbers[2].clone()).is_err());
+        assert!(value_to_bigint(numbers[3].clone()).is_err());
+    }
+
+    #[test]
+    fn multiplier_negative_input() {
+        // -1 * 11 = p - 11
+        let inputs = HashMap::from([("a".to_string(), json!(-1)), ("b".to_string(), json!(11))]);
+
+        run_test(TestCase {
+            circuit_path: root_path("test-vectors/mycircuit.wasm").as_str(),
+            inputs,
+            n64: 4,
+            witness: &[
+                "1",
+                "21888242871839275222246405745257275088548364400416034343698204186575808495606",
+                "21888242871839275222246405745257275088548364400416034343698204186575808495616",
+                "11",
+            ],
+        });
+    }
+
+    #[test]
+    fn test_init_once() {
+        use crate::bellman_ce::pairing::bn256::Bn256;
+        use crate::witness::circom::INIT_CALLS;
+        let mut wtns =
+            WitnessCalculator::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let inputs = vec![
+            ("a".to_string(), vec![BigInt::from(3u32)]),
+            ("b".to_string(), vec![BigInt::from(11u32)]),
+        ];
+        let init_calls = |wtns: &mut WitnessCalculator, f: &dyn Fn(&mut WitnessCalculator)| {
+            let before = INIT_CALLS.with(|c| c.get());
+            f(wtns);
+            INIT_CALLS.with(|c| c.get()) - before
+        };
+        assert_eq!(
+            init_calls(&mut wtns, &|w| {
+                w.calculate_witness(inputs.clone(), true).unwrap();
+            }),
+            1
+        );
+        assert_eq!(
+            init_calls(&mut wtns, &|w| {
+                w.calculate_witness_bin(inputs.clone(), true).unwrap();
+            }),
+            1
+        );
+        assert_eq!(
+            init_calls(&mut wtns, &|w| {
+                w.calculate_witness_fr_parallel::<Bn256, _>(inputs.clone(), true)
+                    .unwrap();
+            }),
+            1
+        );
+    }
+
+    #[test]
+    fn test_check_witness_constant() {
+        let mut wtns =
+            WitnessCalculator::from_file(root_path("test-vectors/mycircuit.wasm")).unwrap();
+        let inputs = vec![
+            ("a".to_string(), vec![BigInt::from(3u32)]),
+            ("b".to_string(), vec![BigInt::from(11u32)]),
+        ];
+        let w = wtns.calculate_witness(inputs, true).unwrap();
+        assert!(check_witness_constant(&w).is_ok());
+
+        let bad = vec![BigInt::from(2u32), BigInt::from(33u32)];
+        for w in [&bad[..], &[]] {
+            let err = check_witness_constant(w).unwrap_err();
+            assert!(matches!(
+                err.downcast_ref::<EigenError>(),
+                Some(EigenError::MalformedWitness(_))
+            ));
+        }
+    }
+
     // TODO: test complex samples
 
     fn run_test(case: TestCase) {
@@ -459,14 +1437,13 @@ mod tests {
             .iter()
             .map(|(key, value)| {
                 let res = match value {
-                    Value::String(inner) => {
-                        vec![BigInt::from_str(inner).unwrap()]
-                    }
-                    Value::Number(inner) => {
-                        vec![BigInt::from(inner.as_u64().expect("not a u32"))]
-                    }
-                    Value::Array(inner) => inner.iter().cloned().map(value_to_bigint).collect(),
-                    _ => panic!(),
+                    Value::Array(inner) => inner
+                        .iter()
+                        .cloned()
+                        .map(value_to_bigint)
+                        .collect::<std::result::Result<_, _>>()
+                        .unwrap(),
+                    _ => vec![value_to_bigint(value.clone()).unwrap()],
                 };
 
                 (key.clone(), res)
@@ -0,0 +1,45 @@
+use program_structure::program_archive::ProgramArchive;
+use program_structure::template_data::TemplateData;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TemplateSignal {
+    pub name: String,
+    /// number of array dimensions, 0 for a single signal
+    pub dimensions: usize,
+}
+
+/// Signature of a circom template
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TemplateInfo {
+    pub name: String,
+    pub params: Vec<String>,
+    pub inputs: Vec<TemplateSignal>,
+    pub outputs: Vec<TemplateSignal>,
+}
+
+pub fn describe_templates(program_archive: &ProgramArchive) -> Vec<TemplateInfo> {
+    let mut templates = program_archive
+        .get_templates()
+        .values()
+        .map(describe_template)
+        .collect::<Vec<_>>();
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+fn describe_template(template: &TemplateData) -> TemplateInfo {
+    let signals = |info: &program_structure::template_data::SignalInfo| {
+        info.iter()
+            .map(|(name, (dimensions, _tags))| TemplateSignal {
+                name: name.clone(),
+                dimensions: *dimensions,
+            })
+            .collect::<Vec<_>>()
+    };
+    TemplateInfo {
+        name: template.get_name().to_string(),
+        params: template.get_name_of_params().clone(),
+        inputs: signals(template.get_inputs()),
+        outputs: signals(template.get_outputs()),
+    }
+}
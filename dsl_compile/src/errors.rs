@@ -7,10 +7,52 @@ pub enum DslError {
     #[error("circom compiler error, '{0}'")]
     CircomCompileError(String),
 
+    #[error("link directory does not exist or is not a directory: {0}")]
+    BadLinkDirectory(String),
+
+    #[error("include depth exceeded: {0}")]
+    IncludeDepthExceeded(String),
+
+    #[error("unsupported prime '{0}', expected one of: {1}")]
+    UnsupportedPrime(String, String),
+
     #[error("Unknown error, `{0}`")]
     Unknown(String),
 }
 
+/// The phase of `circom_compiler` that failed, carrying the error it failed with, usually
+/// a `DslError` to downcast to.
+#[derive(Error, Debug)]
+pub enum CompilePhaseError {
+    #[error("invalid input, {0:#}")]
+    InvalidInput(anyhow::Error),
+
+    #[error("parse failed, {0:#}")]
+    Parse(anyhow::Error),
+
+    #[error("type analysis failed, {0:#}")]
+    TypeAnalysis(anyhow::Error),
+
+    #[error("execution failed, {0:#}")]
+    Execution(anyhow::Error),
+
+    #[error("compilation failed, {0:#}")]
+    Compilation(anyhow::Error),
+}
+
+impl CompilePhaseError {
+    /// The underlying error, whatever the phase.
+    pub fn inner(&self) -> &anyhow::Error {
+        match self {
+            CompilePhaseError::InvalidInput(e)
+            | CompilePhaseError::Parse(e)
+            | CompilePhaseError::TypeAnalysis(e)
+            | CompilePhaseError::Execution(e)
+            | CompilePhaseError::Compilation(e) => e,
+        }
+    }
+}
+
 impl From<String> for DslError {
     fn from(e: String) -> Self {
         DslError::Unknown(e)
@@ -20,6 +20,7 @@ pub struct ExecutionConfig {
     pub json_substitution_flag: bool,
     pub json_constraint_flag: bool,
     pub prime: String,
+    pub progress: Option<crate::CompileProgress>,
 }
 
 pub fn execute_project(program_archive: ProgramArchive, config: ExecutionConfig) -> Result<VCP> {
@@ -41,6 +42,9 @@ pub fn execute_project(program_archive: ProgramArchive, config: ExecutionConfig)
     };
     match build_circuit(program_archive, build_config) {
         Ok((exporter, vcp)) => {
+            if let Some(progress) = &config.progress {
+                progress(crate::CompileStage::GeneratingR1CS);
+            }
             if config.r1cs_flag {
                 generate_output_r1cs(&config.r1cs, exporter.as_ref())?;
             }
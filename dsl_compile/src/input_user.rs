@@ -1,6 +1,7 @@
 use crate::errors::{bail, DslError, Result};
 use ansi_term::Colour;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 #[allow(dead_code)]
 pub struct Input {
@@ -34,6 +35,9 @@ pub struct Input {
     pub flag_verbose: bool,
     pub prime: String,
     pub link_libraries: Vec<PathBuf>,
+    pub max_include_depth: usize,
+    pub output_layout: OutputLayout,
+    pub output_path: PathBuf,
 }
 
 const P_0: &str = "21888242871839275222246405745257275088548364400416034343698204186575808495617";
@@ -45,8 +49,72 @@ const JS: &str = "js";
 const DAT: &str = "dat";
 const SYM: &str = "sym";
 const JSON: &str = "json";
+pub const MAX_INCLUDE_DEPTH: usize = 64;
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+/// Where the compiler outputs go under the output directory.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum OutputLayout {
+    /// everything directly in the output directory
+    Flat,
+    /// the r1cs and sym in the output directory, the wasm and its js helpers under
+    /// `<name>_js/` and the C witness generator under `<name>_cpp/`, like snarkjs
+    #[default]
+    SnarkjsStyle,
+}
+
+/// The fields circom can compile to, parsed from and displayed as circom's `--prime` names.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Prime {
+    Bn128,
+    Bls12381,
+    Goldilocks,
+    Grumpkin,
+    Pallas,
+    Vesta,
+}
+
+impl Prime {
+    pub const ALL: [Prime; 6] = [
+        Prime::Bn128,
+        Prime::Bls12381,
+        Prime::Goldilocks,
+        Prime::Grumpkin,
+        Prime::Pallas,
+        Prime::Vesta,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Prime::Bn128 => "bn128",
+            Prime::Bls12381 => "bls12381",
+            Prime::Goldilocks => "goldilocks",
+            Prime::Grumpkin => "grumpkin",
+            Prime::Pallas => "pallas",
+            Prime::Vesta => "vesta",
+        }
+    }
+}
+
+impl std::fmt::Display for Prime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Prime {
+    type Err = DslError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Prime::ALL
+            .into_iter()
+            .find(|p| p.name() == s)
+            .ok_or_else(|| {
+                DslError::UnsupportedPrime(s.to_string(), Prime::ALL.map(|p| p.name()).join(", "))
+            })
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum SimplificationStyle {
     O0,
     O1,
@@ -84,14 +152,45 @@ impl Input {
         o_style: SimplificationStyle,
         prime: String,
         paths: Vec<String>,
+        layout: OutputLayout,
     ) -> Result<Input> {
         let file_name = input.file_stem().unwrap().to_str().unwrap().to_string();
-        let output_c_path = Input::build_folder(output_path, &file_name, CPP);
-        let output_js_path = Input::build_folder(output_path, &file_name, JS);
+        // circom wipes and recreates the js and c folders, so the flat layout stages them in
+        // hidden folders emptied into the output directory by `finish_layout`
+        let (output_c_path, output_js_path) = match layout {
+            OutputLayout::Flat => (
+                Input::build_folder(output_path, &format!(".{}", file_name), CPP),
+                Input::build_folder(output_path, &format!(".{}", file_name), JS),
+            ),
+            OutputLayout::SnarkjsStyle => (
+                Input::build_folder(output_path, &file_name, CPP),
+                Input::build_folder(output_path, &file_name, JS),
+            ),
+        };
+        let (output_c_files, output_js_files): (&Path, &Path) = match layout {
+            OutputLayout::Flat => (output_path, output_path),
+            OutputLayout::SnarkjsStyle => (&*output_c_path, &*output_js_path),
+        };
+        // a relative link directory is looked up next to the main file first, then from the
+        // working directory
+        let input_dir = input.parent().unwrap_or_else(|| Path::new(""));
+        let resolve = |path: &str| {
+            [input_dir.join(path), PathBuf::from(path)]
+                .into_iter()
+                .find(|candidate| candidate.is_dir())
+        };
         let mut link_libraries: Vec<PathBuf> = vec![];
-        for path in paths.into_iter() {
-            link_libraries.push(Path::new(&path).to_path_buf());
+        let mut bad_paths = vec![];
+        for path in paths.iter() {
+            match resolve(path) {
+                Some(dir) => link_libraries.push(dir),
+                None => bad_paths.push(path.as_str()),
+            }
         }
+        if !bad_paths.is_empty() {
+            bail!(DslError::BadLinkDirectory(bad_paths.join(", ")));
+        }
+        prime.parse::<Prime>()?;
 
         let input = input.to_path_buf();
 
@@ -99,14 +198,14 @@ impl Input {
             field: P_0,
             input_program: input,
             out_r1cs: Input::build_output(output_path, &file_name, R1CS),
-            out_wat_code: Input::build_output(&output_js_path, &file_name, WAT),
-            out_wasm_code: Input::build_output(&output_js_path, &file_name, WASM),
+            out_wat_code: Input::build_output(output_js_files, &file_name, WAT),
+            out_wasm_code: Input::build_output(output_js_files, &file_name, WASM),
             out_js_folder: output_js_path.to_path_buf(),
             out_wasm_name: file_name.clone(),
             out_c_folder: output_c_path.to_path_buf(),
             out_c_run_name: file_name.clone(),
-            out_c_code: Input::build_output(&output_c_path, &file_name, CPP),
-            out_c_dat: Input::build_output(&output_c_path, &file_name, DAT),
+            out_c_code: Input::build_output(output_c_files, &file_name, CPP),
+            out_c_dat: Input::build_output(output_c_files, &file_name, DAT),
             out_sym: Input::build_output(output_path, &file_name, SYM),
             out_json_constraints: Input::build_output(
                 output_path,
@@ -135,9 +234,31 @@ impl Input {
             //prime: "bn128".to_string(), //goldilocks
             prime,
             link_libraries,
+            max_include_depth: MAX_INCLUDE_DEPTH,
+            output_layout: layout,
+            output_path: output_path.to_path_buf(),
         })
     }
 
+    /// Move what circom wrote into the staging folders of the flat layout up into the
+    /// output directory, a no-op for the snarkjs layout.
+    pub fn finish_layout(&self) -> Result<()> {
+        if self.output_layout != OutputLayout::Flat {
+            return Ok(());
+        }
+        for folder in [&self.out_js_folder, &self.out_c_folder] {
+            if !folder.is_dir() {
+                continue;
+            }
+            for entry in std::fs::read_dir(folder)? {
+                let entry = entry?;
+                std::fs::rename(entry.path(), self.output_path.join(entry.file_name()))?;
+            }
+            std::fs::remove_dir(folder)?;
+        }
+        Ok(())
+    }
+
     fn build_folder(output_path: &Path, filename: &str, ext: &str) -> Box<Path> {
         let mut file = output_path.to_path_buf();
         let folder_name = format!("{}_{}", filename, ext);
@@ -238,4 +359,7 @@ impl Input {
     pub fn get_prime(&self) -> String {
         self.prime.clone()
     }
+    pub fn max_include_depth(&self) -> usize {
+        self.max_include_depth
+    }
 }
@@ -1,18 +1,209 @@
 //! It's for: Domain Specific Language(DSL) compiler: now only include circom compile.
 
 use crate::errors::Result;
-use std::path::Path;
+use algebraic::bellman_ce::ScalarEngine;
+use algebraic::circom_circuit::R1CS;
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 mod compilation_user;
+mod describe_user;
 mod errors;
 mod execution_user;
 mod input_user;
 mod parser_user;
 mod type_analysis_user;
 
+pub use describe_user::{TemplateInfo, TemplateSignal};
+pub use errors::{CompilePhaseError, DslError};
+pub use input_user::{OutputLayout, Prime, SimplificationStyle};
+
 /// Align with https://github.com/iden3/circom/blob/master/circom/Cargo.toml#L3
 const CIRCOM_VERSION: &str = "2.1.2";
 
+/// What `circom_compiler` did, for reproducibility records.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompilationReport {
+    /// the style resolved from the simplification flags, `O2(usize::MAX)` for `full`
+    pub simplification: SimplificationStyle,
+    pub constraints: usize,
+    pub wires: usize,
+    pub public_outputs: usize,
+    pub public_inputs: usize,
+    pub private_inputs: usize,
+    /// the files written, except circom's js and C helpers
+    pub artifacts: Vec<String>,
+    pub elapsed: Duration,
+    /// the process' peak resident memory in bytes during each phase, `parse`,
+    /// `type_analysis`, `execution` and `compilation`, empty unless
+    /// `CircomCompileOptions::measure_memory` is set and the platform can measure it, which
+    /// only Linux does
+    pub peak_memory: Vec<(&'static str, u64)>,
+}
+
+/// Bumped on any change of `CompilationReport::to_json`'s output other than a new field.
+pub const COMPILATION_REPORT_SCHEMA_VERSION: u32 = 1;
+
+impl CompilationReport {
+    /// The report as a JSON object carrying `schema_version`, for CI dashboards and build
+    /// caches.
+    pub fn to_json(&self) -> String {
+        let simplification = match self.simplification {
+            SimplificationStyle::O0 => "O0".to_string(),
+            SimplificationStyle::O1 => "O1".to_string(),
+            SimplificationStyle::O2(usize::MAX) => "O2(full)".to_string(),
+            SimplificationStyle::O2(rounds) => format!("O2({})", rounds),
+        };
+        serde_json::json!({
+            "schema_version": COMPILATION_REPORT_SCHEMA_VERSION,
+            "simplification": simplification,
+            "constraints": self.constraints,
+            "signals": {
+                "wires": self.wires,
+                "public_outputs": self.public_outputs,
+                "public_inputs": self.public_inputs,
+                "private_inputs": self.private_inputs,
+            },
+            "artifacts": self.artifacts,
+            "timings": {
+                "total_ms": self.elapsed.as_millis() as u64,
+            },
+            "peak_memory": self
+                .peak_memory
+                .iter()
+                .map(|(phase, bytes)| (phase.to_string(), serde_json::json!(bytes)))
+                .collect::<serde_json::Map<_, _>>(),
+        })
+        .to_string()
+    }
+}
+
+/// Held by the compilations measuring their memory, so they don't reset each other's peak.
+static MEMORY_PROBE: Mutex<()> = Mutex::new(());
+
+/// Reset the peak resident set size `peak_rss` reports, false where that's not possible,
+/// which includes anything but Linux.
+fn reset_peak_rss() -> bool {
+    std::fs::write("/proc/self/clear_refs", "5").is_ok()
+}
+
+/// Peak resident set size of the process since the last `reset_peak_rss`, from `VmHWM` in
+/// `/proc/self/status`, so always `None` outside Linux.
+fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kb = status
+        .lines()
+        .find_map(|l| l.strip_prefix("VmHWM:"))?
+        .trim()
+        .strip_suffix("kB")?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// The stages of `circom_compiler_with`, reported to `CircomCompileOptions::progress` as
+/// each one starts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompileStage {
+    Parsing,
+    TypeAnalysis,
+    /// constraint generation and simplification
+    Executing,
+    /// writing the r1cs and sym
+    GeneratingR1CS,
+    /// only if `wasm_flag` is set
+    GeneratingWasm,
+    Done,
+}
+
+/// Called with each `CompileStage`, from the thread running the compilation.
+pub type CompileProgress = Arc<dyn Fn(CompileStage) + Send + Sync>;
+
+/// Options of `circom_compiler_with`, the output paths are all derived from `output` and
+/// `output_layout`.
+#[derive(Clone)]
+pub struct CircomCompileOptions {
+    pub input: String,
+    pub output: String,
+    pub prime: String,
+    pub link_directories: Vec<String>,
+    pub simplification: SimplificationStyle,
+    pub output_layout: OutputLayout,
+    /// write the C++ witness generator and its `.dat`
+    pub c_flag: bool,
+    /// write the wasm witness generator
+    pub wasm_flag: bool,
+    /// keep the `.wat` the wasm is assembled from
+    pub wat_flag: bool,
+    pub progress: Option<CompileProgress>,
+    /// fill `CompilationReport::peak_memory`. The figures are for the whole process, so
+    /// measured compilations run one at a time, but anything else running meanwhile,
+    /// including compilations not measuring, e.g. in `compile_many`, counts too.
+    pub measure_memory: bool,
+}
+
+impl std::fmt::Debug for CircomCompileOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CircomCompileOptions")
+            .field("input", &self.input)
+            .field("output", &self.output)
+            .field("prime", &self.prime)
+            .field("link_directories", &self.link_directories)
+            .field("simplification", &self.simplification)
+            .field("output_layout", &self.output_layout)
+            .field("c_flag", &self.c_flag)
+            .field("wasm_flag", &self.wasm_flag)
+            .field("wat_flag", &self.wat_flag)
+            .field("progress", &self.progress.is_some())
+            .field("measure_memory", &self.measure_memory)
+            .finish()
+    }
+}
+
+/// bn128 with O1 simplification in the snarkjs layout, only the wasm witness generator, like
+/// circom's defaults, compiling nothing until `input` is set.
+impl Default for CircomCompileOptions {
+    fn default() -> Self {
+        CircomCompileOptions {
+            input: String::new(),
+            output: ".".to_string(),
+            prime: "bn128".to_string(),
+            link_directories: vec![],
+            simplification: SimplificationStyle::O1,
+            output_layout: OutputLayout::default(),
+            c_flag: false,
+            wasm_flag: true,
+            wat_flag: false,
+            progress: None,
+            measure_memory: false,
+        }
+    }
+}
+
+impl CircomCompileOptions {
+    pub fn new(input: String, output: String) -> Self {
+        CircomCompileOptions {
+            input,
+            output,
+            ..Default::default()
+        }
+    }
+
+    pub fn output_layout(mut self, layout: OutputLayout) -> Self {
+        self.output_layout = layout;
+        self
+    }
+
+    pub fn progress(mut self, progress: impl Fn(CompileStage) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+}
+
 /// Compile circom circuits to r1cs, and generate witness
 pub fn circom_compiler(
     input: String,
@@ -22,9 +213,7 @@ pub fn circom_compiler(
     output: String,
     no_simplification: bool,
     reduced_simplification: bool,
-) -> Result<()> {
-    use compilation_user::CompilerConfig;
-    use execution_user::ExecutionConfig;
+) -> std::result::Result<CompilationReport, CompilePhaseError> {
     let fullopt = !full_simplification.is_empty();
     let o2_arg = full_simplification.as_str();
     let o_style = input_user::get_simplification_style(
@@ -32,14 +221,71 @@ pub fn circom_compiler(
         reduced_simplification,
         fullopt,
         o2_arg,
-    )?;
-    let input = Path::new(&input);
-    let output = Path::new(&output);
+    )
+    .map_err(CompilePhaseError::InvalidInput)?;
+    circom_compiler_with(CircomCompileOptions {
+        prime,
+        link_directories,
+        simplification: o_style,
+        ..CircomCompileOptions::new(input, output)
+    })
+}
 
-    let user_input = input_user::Input::new(input, output, o_style, prime, link_directories)?;
-    let mut program_archive = parser_user::parse_project(&user_input)?;
+/// Same as `circom_compiler`, with the options in one struct
+pub fn circom_compiler_with(
+    options: CircomCompileOptions,
+) -> std::result::Result<CompilationReport, CompilePhaseError> {
+    use compilation_user::CompilerConfig;
+    use execution_user::ExecutionConfig;
+    let start = Instant::now();
+    let _probe = options
+        .measure_memory
+        .then(|| MEMORY_PROBE.lock().unwrap_or_else(|e| e.into_inner()));
+    let mut measuring = options.measure_memory && reset_peak_rss();
+    let mut peak_memory = vec![];
+    let mut record_phase = |phase: &'static str| {
+        if measuring {
+            // reset for the next phase, or give up on all of them
+            match peak_rss() {
+                Some(bytes) if reset_peak_rss() => peak_memory.push((phase, bytes)),
+                _ => {
+                    peak_memory.clear();
+                    measuring = false;
+                }
+            }
+        }
+    };
+    let progress = options.progress.clone();
+    let stage = |stage: CompileStage| {
+        if let Some(progress) = &progress {
+            progress(stage);
+        }
+    };
+    let o_style = options.simplification;
+    let input = Path::new(&options.input);
+    let output = Path::new(&options.output);
 
-    type_analysis_user::analyse_project(&mut program_archive)?;
+    let mut user_input = input_user::Input::new(
+        input,
+        output,
+        o_style,
+        options.prime,
+        options.link_directories,
+        options.output_layout,
+    )
+    .map_err(CompilePhaseError::InvalidInput)?;
+    user_input.c_flag = options.c_flag;
+    user_input.wasm_flag = options.wasm_flag;
+    user_input.wat_flag = options.wat_flag;
+    stage(CompileStage::Parsing);
+    let mut program_archive =
+        parser_user::parse_project(&user_input).map_err(CompilePhaseError::Parse)?;
+    record_phase("parse");
+
+    stage(CompileStage::TypeAnalysis);
+    type_analysis_user::analyse_project(&mut program_archive)
+        .map_err(CompilePhaseError::TypeAnalysis)?;
+    record_phase("type_analysis");
 
     let config = ExecutionConfig {
         no_rounds: user_input.no_rounds(),
@@ -56,8 +302,12 @@ pub fn circom_compiler(
         r1cs: user_input.r1cs_file().to_string(),
         json_constraints: user_input.json_constraints_file().to_string(),
         prime: user_input.get_prime(),
+        progress: progress.clone(),
     };
-    let circuit = execution_user::execute_project(program_archive, config)?;
+    stage(CompileStage::Executing);
+    let circuit = execution_user::execute_project(program_archive, config)
+        .map_err(CompilePhaseError::Execution)?;
+    record_phase("execution");
     let compilation_config = CompilerConfig {
         vcp: circuit,
         debug_output: user_input.print_ir_flag(),
@@ -74,6 +324,770 @@ pub fn circom_compiler(
         wasm_file: user_input.wasm_file().to_string(),
         produce_input_log: user_input.main_inputs_flag(),
     };
-    compilation_user::compile(compilation_config)?;
-    Result::Ok(())
+    if user_input.wasm_flag() {
+        stage(CompileStage::GeneratingWasm);
+    }
+    compilation_user::compile(compilation_config).map_err(CompilePhaseError::Compilation)?;
+    record_phase("compilation");
+    user_input
+        .finish_layout()
+        .map_err(CompilePhaseError::Compilation)?;
+
+    let header = std::fs::File::open(user_input.r1cs_file())
+        .and_then(|f| algebraic::r1cs_file::header_from_reader(std::io::BufReader::new(f)))
+        .map_err(|e| CompilePhaseError::Compilation(e.into()))?;
+    stage(CompileStage::Done);
+    let artifacts = [
+        user_input.r1cs_file(),
+        user_input.sym_file(),
+        user_input.wasm_file(),
+        user_input.wat_file(),
+        user_input.c_file(),
+        user_input.dat_file(),
+    ]
+    .iter()
+    .filter(|f| Path::new(f).is_file())
+    .map(|f| f.to_string())
+    .collect();
+    Ok(CompilationReport {
+        simplification: o_style,
+        constraints: header.n_constraints as usize,
+        wires: header.n_wires as usize,
+        public_outputs: header.n_pub_out as usize,
+        public_inputs: header.n_pub_in as usize,
+        private_inputs: header.n_prv_in as usize,
+        artifacts,
+        elapsed: start.elapsed(),
+        peak_memory,
+    })
+}
+
+/// What `compile_to_buffers` produced.
+#[derive(Clone, Debug)]
+pub struct CompiledArtifacts {
+    pub r1cs: Vec<u8>,
+    /// `None` unless `wasm_flag` is set
+    pub wasm: Option<Vec<u8>>,
+    pub sym: Option<String>,
+}
+
+/// Same as `circom_compiler_with`, returning the r1cs, wasm and sym as buffers, ready for
+/// `reader::load_r1cs_from_bin` and `WitnessCalculator::from_bytes`. `output` and
+/// `output_layout` are ignored.
+///
+/// This is not an in-memory compilation: circom's writers can only write to a path, so the
+/// artifacts go through a scratch folder under the system temp dir, read back and removed
+/// on a best effort basis before returning.
+pub fn compile_to_buffers(
+    options: CircomCompileOptions,
+) -> std::result::Result<CompiledArtifacts, CompilePhaseError> {
+    let io_error = |e: std::io::Error| CompilePhaseError::Compilation(e.into());
+    let scratch = ScratchDir::new(&options.input).map_err(io_error)?;
+    let output = scratch
+        .path()
+        .to_str()
+        .ok_or_else(|| {
+            CompilePhaseError::InvalidInput(anyhow::anyhow!(
+                "temp dir {} is not valid UTF-8",
+                scratch.path().display()
+            ))
+        })?
+        .to_string();
+    circom_compiler_with(CircomCompileOptions {
+        output,
+        output_layout: OutputLayout::Flat,
+        ..options
+    })
+    .and_then(|report| {
+        let artifact = |ext: &str| {
+            report
+                .artifacts
+                .iter()
+                .find(|f| Path::new(f).extension().map_or(false, |e| e == ext))
+        };
+        let r1cs = artifact("r1cs").ok_or_else(|| {
+            CompilePhaseError::Compilation(anyhow::anyhow!("no r1cs was written"))
+        })?;
+        Ok(CompiledArtifacts {
+            r1cs: std::fs::read(r1cs).map_err(io_error)?,
+            wasm: artifact("wasm")
+                .map(std::fs::read)
+                .transpose()
+                .map_err(io_error)?,
+            sym: artifact("sym")
+                .map(std::fs::read_to_string)
+                .transpose()
+                .map_err(io_error)?,
+        })
+    })
+}
+
+/// Run independent `circom_compiler_with` jobs in parallel on the rayon thread pool, returning
+/// the results in the order of `jobs`.
+///
+/// Each job is a separate parse, execution and code generation pipeline, only their files
+/// could collide, so a job writing the same circuit to the same output folder as an
+/// earlier one fails without running.
+pub fn compile_many(
+    jobs: Vec<CircomCompileOptions>,
+) -> Vec<std::result::Result<CompilationReport, CompilePhaseError>> {
+    let mut outputs = HashSet::new();
+    let duplicated = jobs
+        .iter()
+        .map(|job| {
+            let input = Path::new(&job.input);
+            let target: PathBuf =
+                Path::new(&job.output).join(input.file_stem().unwrap_or_default());
+            !outputs.insert(target)
+        })
+        .collect::<Vec<_>>();
+    jobs.into_par_iter()
+        .zip(duplicated)
+        .map(|(job, duplicated)| {
+            if duplicated {
+                return Err(CompilePhaseError::InvalidInput(
+                    DslError::CircomCompileError(format!(
+                        "{} is already compiled to {} by another job",
+                        job.input, job.output
+                    ))
+                    .into(),
+                ));
+            }
+            circom_compiler_with(job)
+        })
+        .collect()
+}
+
+/// Compile circom circuits to an in-memory R1CS, without generating the witness calculator.
+pub fn circom_compile_r1cs<E: ScalarEngine>(
+    input: String,
+    prime: String,
+    link_directories: Vec<String>,
+) -> Result<R1CS<E>> {
+    let o_style = input_user::get_simplification_style(false, false, true, "full")?;
+    let r1cs = execute_to_r1cs(&input, prime, link_directories, o_style)?;
+    let (r1cs, _wire_mapping) =
+        algebraic::reader::load_r1cs_from_bin::<_, E>(std::io::Cursor::new(r1cs));
+    Ok(r1cs)
+}
+
+/// Constraint counts of a circuit at each simplification level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimplificationComparison {
+    /// `O0`
+    pub none: usize,
+    /// `O1`
+    pub reduced: usize,
+    /// `O2(full)`
+    pub full: usize,
+}
+
+/// Run the constraint generation of `input` at `O0`, `O1` and full `O2`, to see what each
+/// level saves, without generating the witness calculator.
+pub fn compare_simplification(
+    input: String,
+    prime: String,
+    link_directories: Vec<String>,
+) -> Result<SimplificationComparison> {
+    let count = |o_style| -> Result<usize> {
+        let r1cs = execute_to_r1cs(&input, prime.clone(), link_directories.clone(), o_style)?;
+        let header = algebraic::r1cs_file::header_from_reader(std::io::Cursor::new(r1cs))?;
+        Ok(header.n_constraints as usize)
+    };
+    Ok(SimplificationComparison {
+        none: count(SimplificationStyle::O0)?,
+        reduced: count(SimplificationStyle::O1)?,
+        full: count(SimplificationStyle::O2(usize::MAX))?,
+    })
+}
+
+/// Parse, type check and execute `input`, returning the r1cs file's bytes.
+///
+/// circom's constraint exporter can only write to a path, so the r1cs goes through a
+/// `ScratchDir`.
+fn execute_to_r1cs(
+    input: &str,
+    prime: String,
+    link_directories: Vec<String>,
+    o_style: SimplificationStyle,
+) -> Result<Vec<u8>> {
+    use execution_user::ExecutionConfig;
+    let scratch = ScratchDir::new(input)?;
+    let user_input = input_user::Input::new(
+        Path::new(input),
+        scratch.path(),
+        o_style,
+        prime,
+        link_directories,
+        OutputLayout::default(),
+    )?;
+    let mut program_archive = parser_user::parse_project(&user_input)?;
+
+    type_analysis_user::analyse_project(&mut program_archive)?;
+
+    let config = ExecutionConfig {
+        no_rounds: user_input.no_rounds(),
+        flag_p: user_input.parallel_simplification_flag(),
+        flag_s: user_input.reduced_simplification_flag(),
+        flag_f: user_input.unsimplified_flag(),
+        flag_verbose: user_input.flag_verbose(),
+        inspect_constraints_flag: user_input.inspect_constraints_flag(),
+        r1cs_flag: true,
+        json_constraint_flag: false,
+        json_substitution_flag: false,
+        sym_flag: false,
+        sym: user_input.sym_file().to_string(),
+        r1cs: user_input.r1cs_file().to_string(),
+        json_constraints: user_input.json_constraints_file().to_string(),
+        prime: user_input.get_prime(),
+        progress: None,
+    };
+    execution_user::execute_project(program_archive, config)?;
+    Ok(std::fs::read(user_input.r1cs_file())?)
+}
+
+/// A fresh folder under the system temp dir for the files of compiling `input`, unique per
+/// call since concurrent compilations in a process mustn't share it.
+///
+/// It is removed on a best effort basis when dropped, whichever way the compilation ended: a
+/// leftover folder is no reason to fail, nor to hide a compile error.
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new(input: &str) -> std::io::Result<Self> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static SCRATCH_ID: AtomicUsize = AtomicUsize::new(0);
+        let stem = Path::new(input).file_stem().unwrap_or_default();
+        let path = std::env::temp_dir().join(format!(
+            "eigen_dsl_compile_{}_{}_{}",
+            std::process::id(),
+            SCRATCH_ID.fetch_add(1, Ordering::Relaxed),
+            stem.to_string_lossy()
+        ));
+        std::fs::create_dir_all(&path)?;
+        Ok(ScratchDir(path))
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Parse and type check a circom project, returning the signature of every template in it,
+/// not only the main component's.
+pub fn parse_and_describe(
+    input: String,
+    link_directories: Vec<String>,
+) -> Result<Vec<TemplateInfo>> {
+    let o_style = input_user::get_simplification_style(false, true, false, "")?;
+    let input = Path::new(&input);
+    // nothing is written, the output path is only recorded
+    let output = std::env::temp_dir();
+    let user_input = input_user::Input::new(
+        input,
+        &output,
+        o_style,
+        "bn128".to_string(),
+        link_directories,
+        OutputLayout::default(),
+    )?;
+    let mut program_archive = parser_user::parse_project(&user_input)?;
+    type_analysis_user::analyse_project(&mut program_archive)?;
+    Ok(describe_user::describe_templates(&program_archive))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use algebraic::bellman_ce::pairing::bn256::Bn256;
+
+    const CIRCUIT_FILE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../algebraic/test-vectors/mycircuit.circom"
+    );
+    const R1CS_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../test/multiplier.r1cs");
+
+    #[test]
+    fn test_circom_compile_r1cs() {
+        let r1cs =
+            circom_compile_r1cs::<Bn256>(CIRCUIT_FILE.to_string(), "bn128".to_string(), vec![])
+                .unwrap();
+        let expected = algebraic::reader::load_r1cs::<Bn256>(R1CS_FILE);
+        assert_eq!(r1cs.num_inputs, expected.num_inputs);
+        assert_eq!(r1cs.num_aux, expected.num_aux);
+        assert_eq!(r1cs.num_variables, expected.num_variables);
+        assert_eq!(r1cs.constraints.len(), expected.constraints.len());
+    }
+
+    #[test]
+    fn test_compile_to_buffers() {
+        let artifacts = compile_to_buffers(CircomCompileOptions {
+            input: CIRCUIT_FILE.to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let (r1cs, _) = algebraic::reader::load_r1cs_from_bin::<_, Bn256>(std::io::Cursor::new(
+            &artifacts.r1cs,
+        ));
+        let expected = algebraic::reader::load_r1cs::<Bn256>(R1CS_FILE);
+        assert_eq!(r1cs.num_inputs, expected.num_inputs);
+        assert_eq!(r1cs.num_variables, expected.num_variables);
+        assert_eq!(r1cs.constraints.len(), expected.constraints.len());
+        assert!(artifacts.wasm.unwrap().starts_with(b"\0asm"));
+        let symbols = algebraic::witness::SymbolTable::parse(&artifacts.sym.unwrap()).unwrap();
+        assert_eq!(symbols.witness_index("c"), Some(1));
+
+        let no_wasm = compile_to_buffers(CircomCompileOptions {
+            input: CIRCUIT_FILE.to_string(),
+            wasm_flag: false,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(no_wasm.wasm.is_none());
+    }
+
+    #[test]
+    fn test_compare_simplification() {
+        let dir = std::env::temp_dir().join(format!("eigen_dsl_compare_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // `e <== b` is simplified away from O1 on
+        let circuit = dir.join("compare.circom");
+        std::fs::write(
+            &circuit,
+            "pragma circom 2.0.0;\n\ntemplate T() {\n    signal input a;\n    signal input b;\n    signal output c;\n    signal output d;\n    signal e;\n    e <== b;\n    c <== a * e;\n    d <== c + 1;\n}\n\ncomponent main = T();\n",
+        )
+        .unwrap();
+        let counts = compare_simplification(
+            circuit.to_str().unwrap().to_string(),
+            "bn128".to_string(),
+            vec![],
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+        let counts = counts.unwrap();
+        assert!(counts.none >= counts.reduced);
+        assert!(counts.reduced >= counts.full);
+        assert!(counts.none > counts.full, "{:?}", counts);
+
+        let multiplier =
+            compare_simplification(CIRCUIT_FILE.to_string(), "bn128".to_string(), vec![]).unwrap();
+        assert_eq!(multiplier.full, 1);
+        assert!(multiplier.none >= multiplier.full);
+    }
+
+    #[test]
+    fn test_compare_simplification_parse_error() {
+        let dir = std::env::temp_dir().join(format!("eigen_dsl_broken_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let circuit = dir.join("compare_broken.circom");
+        std::fs::write(
+            &circuit,
+            "pragma circom 2.0.0;\n\ntemplate T() {\n    signal input a\n}\n\ncomponent main = T();\n",
+        )
+        .unwrap();
+        let counts = compare_simplification(
+            circuit.to_str().unwrap().to_string(),
+            "bn128".to_string(),
+            vec![],
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(counts.is_err());
+
+        // the scratch folders are named after the circuit
+        let prefix = format!("eigen_dsl_compile_{}_", std::process::id());
+        let leftover = std::fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with(&prefix) && name.ends_with("_compare_broken"))
+            .collect::<Vec<_>>();
+        assert!(leftover.is_empty(), "{:?}", leftover);
+    }
+
+    #[test]
+    fn test_compilation_report() {
+        let output = std::env::temp_dir().join(format!("eigen_dsl_report_{}", std::process::id()));
+        std::fs::create_dir_all(&output).unwrap();
+        let o1 = circom_compiler_with(CircomCompileOptions {
+            measure_memory: true,
+            ..CircomCompileOptions::new(
+                CIRCUIT_FILE.to_string(),
+                output.to_str().unwrap().to_string(),
+            )
+        });
+        let o2 = circom_compiler(
+            CIRCUIT_FILE.to_string(),
+            "bn128".to_string(),
+            "2".to_string(),
+            vec![],
+            output.to_str().unwrap().to_string(),
+            false,
+            false,
+        );
+        std::fs::remove_dir_all(&output).unwrap();
+        let (o1, o2) = (o1.unwrap(), o2.unwrap());
+        assert_eq!(o1.simplification, SimplificationStyle::O1);
+        assert_eq!(o2.simplification, SimplificationStyle::O2(2));
+        assert!(o2.peak_memory.is_empty());
+
+        let expected = algebraic::reader::load_r1cs::<Bn256>(R1CS_FILE);
+        assert_eq!(o1.constraints, expected.constraints.len());
+        assert_eq!(o1.private_inputs, expected.num_prv_inputs);
+        assert!(o1.artifacts.iter().any(|f| f.ends_with("mycircuit.r1cs")));
+        let json: serde_json::Value = serde_json::from_str(&o1.to_json()).unwrap();
+        assert_eq!(json["schema_version"], COMPILATION_REPORT_SCHEMA_VERSION);
+        assert_eq!(json["simplification"], "O1");
+        assert_eq!(json["constraints"], expected.constraints.len());
+        if cfg!(target_os = "linux") {
+            assert_eq!(
+                o1.peak_memory.iter().map(|(p, _)| *p).collect::<Vec<_>>(),
+                vec!["parse", "type_analysis", "execution", "compilation"]
+            );
+            assert!(o1.peak_memory.iter().all(|(_, bytes)| *bytes > 0));
+            assert!(json["peak_memory"]["execution"].as_u64().unwrap() > 0);
+        }
+    }
+
+    #[test]
+    fn test_output_layout() {
+        let output = std::env::temp_dir().join(format!("eigen_dsl_layout_{}", std::process::id()));
+        let compile = |layout: OutputLayout| {
+            let dir = output.join(format!("{:?}", layout));
+            std::fs::create_dir_all(&dir).unwrap();
+            circom_compiler_with(
+                CircomCompileOptions::new(
+                    CIRCUIT_FILE.to_string(),
+                    dir.to_str().unwrap().to_string(),
+                )
+                .output_layout(layout),
+            )
+            .map(|_| dir)
+        };
+        let snarkjs = compile(OutputLayout::SnarkjsStyle);
+        let flat = compile(OutputLayout::Flat);
+        let snarkjs_wasm = snarkjs
+            .as_ref()
+            .map(|dir| dir.join("mycircuit_js/mycircuit.wasm").is_file());
+        let flat_wasm = flat.as_ref().map(|dir| {
+            dir.join("mycircuit.wasm").is_file() && dir.join("mycircuit.r1cs").is_file()
+        });
+        std::fs::remove_dir_all(&output).unwrap();
+        assert!(snarkjs_wasm.unwrap());
+        assert!(flat_wasm.unwrap());
+    }
+
+    #[test]
+    fn test_compile_progress() {
+        let output =
+            std::env::temp_dir().join(format!("eigen_dsl_progress_{}", std::process::id()));
+        std::fs::create_dir_all(&output).unwrap();
+        let stages = Arc::new(std::sync::Mutex::new(vec![]));
+        let recorded = stages.clone();
+        let report = circom_compiler_with(
+            CircomCompileOptions::new(
+                CIRCUIT_FILE.to_string(),
+                output.to_str().unwrap().to_string(),
+            )
+            .progress(move |stage| recorded.lock().unwrap().push(stage)),
+        );
+        std::fs::remove_dir_all(&output).unwrap();
+        report.unwrap();
+        assert_eq!(
+            *stages.lock().unwrap(),
+            vec![
+                CompileStage::Parsing,
+                CompileStage::TypeAnalysis,
+                CompileStage::Executing,
+                CompileStage::GeneratingR1CS,
+                CompileStage::GeneratingWasm,
+                CompileStage::Done,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_targets() {
+        let output = std::env::temp_dir().join(format!("eigen_dsl_targets_{}", std::process::id()));
+        std::fs::create_dir_all(&output).unwrap();
+        let report = circom_compiler_with(CircomCompileOptions {
+            c_flag: true,
+            wat_flag: true,
+            output_layout: OutputLayout::Flat,
+            ..CircomCompileOptions::new(
+                CIRCUIT_FILE.to_string(),
+                output.to_str().unwrap().to_string(),
+            )
+        });
+        let written = ["wasm", "wat", "cpp", "dat"]
+            .map(|ext| output.join(format!("mycircuit.{}", ext)).is_file());
+        std::fs::remove_dir_all(&output).unwrap();
+        assert_eq!(report.unwrap().artifacts.len(), 6);
+        assert_eq!(written, [true; 4]);
+    }
+
+    #[test]
+    fn test_compile_many() {
+        let dir = std::env::temp_dir().join(format!("eigen_dsl_many_{}", std::process::id()));
+        // the three jobs only start parsing once all of them got there, so they do run at
+        // the same time
+        let started = Arc::new(std::sync::Barrier::new(3));
+        let mut jobs = vec![];
+        for n in 1..=3 {
+            let job_dir = dir.join(format!("job{}", n));
+            std::fs::create_dir_all(&job_dir).unwrap();
+            // a chain of n multiplications, n constraints
+            let mut body =
+                "    signal input x[4];\n    signal output y[4];\n    y[0] <== x[0];\n".to_string();
+            for i in 1..=n {
+                body += &format!("    y[{}] <== y[{}] * x[{}];\n", i, i - 1, i);
+            }
+            for i in (n + 1)..4 {
+                body += &format!("    y[{}] <== 0;\n", i);
+            }
+            let circuit = job_dir.join(format!("chain{}.circom", n));
+            std::fs::write(
+                &circuit,
+                format!(
+                    "pragma circom 2.0.0;\n\ntemplate Chain() {{\n{}}}\n\ncomponent main = Chain();\n",
+                    body
+                ),
+            )
+            .unwrap();
+            let started = started.clone();
+            jobs.push(
+                CircomCompileOptions::new(
+                    circuit.to_str().unwrap().to_string(),
+                    job_dir.to_str().unwrap().to_string(),
+                )
+                .progress(move |stage| {
+                    if stage == CompileStage::Parsing {
+                        started.wait();
+                    }
+                }),
+            );
+        }
+        jobs.push(jobs[0].clone());
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(3)
+            .build()
+            .unwrap();
+        let reports = pool.install(|| compile_many(jobs));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(reports.len(), 4);
+        for (n, report) in reports.iter().take(3).enumerate() {
+            let report = report.as_ref().unwrap();
+            assert_eq!(report.constraints, n + 1);
+            assert!(report
+                .artifacts
+                .iter()
+                .any(|f| f.ends_with(&format!("chain{}.r1cs", n + 1))));
+        }
+        match &reports[3] {
+            Err(CompilePhaseError::InvalidInput(e)) => assert!(matches!(
+                e.downcast_ref::<DslError>(),
+                Some(DslError::CircomCompileError(_))
+            )),
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_circom_compile_error() {
+        let dir = std::env::temp_dir().join(format!("eigen_dsl_error_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let compile = |name: &str, source: &str| {
+            let circuit = dir.join(name);
+            std::fs::write(&circuit, source).unwrap();
+            circom_compiler_with(CircomCompileOptions::new(
+                circuit.to_str().unwrap().to_string(),
+                dir.to_str().unwrap().to_string(),
+            ))
+        };
+        let parse = compile("parse.circom", "pragma circom 2.0.0;\ntemplate T( {\n");
+        let type_analysis = compile(
+            "types.circom",
+            "pragma circom 2.0.0;\ntemplate T() {\n    signal input a;\n    signal output b;\n    b <== a * c;\n}\ncomponent main = T();\n",
+        );
+        let invalid_input = circom_compiler(
+            CIRCUIT_FILE.to_string(),
+            "bn128".to_string(),
+            "not_a_number".to_string(),
+            vec![],
+            dir.to_str().unwrap().to_string(),
+            false,
+            false,
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(parse, Err(CompilePhaseError::Parse(_))));
+        assert!(matches!(
+            type_analysis,
+            Err(CompilePhaseError::TypeAnalysis(_))
+        ));
+        let err = invalid_input.unwrap_err();
+        assert!(matches!(err, CompilePhaseError::InvalidInput(_)));
+        assert!(err.inner().downcast_ref::<DslError>().is_some());
+    }
+
+    #[test]
+    fn test_bad_link_directory() {
+        let missing = concat!(env!("CARGO_MANIFEST_DIR"), "/no_such_dir").to_string();
+        let err = circom_compile_r1cs::<Bn256>(
+            CIRCUIT_FILE.to_string(),
+            "bn128".to_string(),
+            vec![missing.clone()],
+        )
+        .unwrap_err();
+        match err.downcast_ref::<DslError>() {
+            Some(DslError::BadLinkDirectory(path)) => assert_eq!(path, &missing),
+            _ => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_link_directories() {
+        let dir = std::env::temp_dir().join(format!("eigen_dsl_link_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(
+            dir.join("lib/square.circom"),
+            "pragma circom 2.0.0;\n\ntemplate Square() {\n    signal input a;\n    signal output b;\n    b <== a * a;\n}\n",
+        )
+        .unwrap();
+        let circuit = dir.join("link.circom");
+        std::fs::write(
+            &circuit,
+            "pragma circom 2.0.0;\n\ninclude \"square.circom\";\n\ncomponent main = Square();\n",
+        )
+        .unwrap();
+        let out = dir.join("out");
+        std::fs::create_dir_all(&out).unwrap();
+        let compile = |link_directories: Vec<String>| {
+            circom_compiler_with(CircomCompileOptions {
+                link_directories,
+                ..CircomCompileOptions::new(
+                    circuit.to_str().unwrap().to_string(),
+                    out.to_str().unwrap().to_string(),
+                )
+            })
+        };
+        let manifest_dir = env!("CARGO_MANIFEST_DIR").to_string();
+        // `lib` is relative to the main file, not to the working directory
+        let resolved = compile(vec![manifest_dir.clone(), "lib".to_string()]);
+        let missing = compile(vec![
+            manifest_dir,
+            "lib".to_string(),
+            "no_such_lib".to_string(),
+            "/no/such/lib".to_string(),
+        ]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(resolved.unwrap().constraints, 1);
+        let err = missing.unwrap_err();
+        assert!(matches!(err, CompilePhaseError::InvalidInput(_)));
+        match err.inner().downcast_ref::<DslError>() {
+            Some(DslError::BadLinkDirectory(paths)) => {
+                assert_eq!(paths, "no_such_lib, /no/such/lib")
+            }
+            _ => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_prime() {
+        assert_eq!("goldilocks".parse::<Prime>().unwrap(), Prime::Goldilocks);
+        let output = std::env::temp_dir().join(format!("eigen_dsl_prime_{}", std::process::id()));
+        std::fs::create_dir_all(&output).unwrap();
+        let compile = |prime: &str| {
+            circom_compiler_with(CircomCompileOptions {
+                prime: prime.to_string(),
+                wasm_flag: false,
+                ..CircomCompileOptions::new(
+                    CIRCUIT_FILE.to_string(),
+                    output.to_str().unwrap().to_string(),
+                )
+            })
+        };
+        let supported = Prime::ALL.map(|p| (p, compile(&p.to_string())));
+        let bogus = compile("bn254");
+        std::fs::remove_dir_all(&output).unwrap();
+        for (prime, report) in supported {
+            assert_eq!(prime.to_string().parse::<Prime>().unwrap(), prime);
+            assert_eq!(report.unwrap().constraints, 1, "{}", prime);
+        }
+        let err = bogus.unwrap_err();
+        assert!(matches!(err, CompilePhaseError::InvalidInput(_)));
+        match err.inner().downcast_ref::<DslError>() {
+            Some(DslError::UnsupportedPrime(prime, supported)) => {
+                assert_eq!(prime, "bn254");
+                assert_eq!(
+                    supported,
+                    "bn128, bls12381, goldilocks, grumpkin, pallas, vesta"
+                );
+            }
+            _ => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_parse_and_describe() {
+        let dir = std::env::temp_dir().join(format!("eigen_dsl_describe_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let circuit = dir.join("describe.circom");
+        std::fs::write(
+            &circuit,
+            r#"pragma circom 2.0.0;
+
+template Sum(n) {
+    signal input in[n];
+    signal output out;
+    var acc = 0;
+    for (var i = 0; i < n; i++) {
+        acc += in[i];
+    }
+    out <== acc;
+}
+
+template Multiplier() {
+    signal input a;
+    signal input b;
+    signal output c;
+    component s = Sum(2);
+    s.in[0] <== a;
+    s.in[1] <== b;
+    c <== a * b + s.out;
+}
+
+component main = Multiplier();
+"#,
+        )
+        .unwrap();
+
+        let templates = parse_and_describe(circuit.to_str().unwrap().to_string(), vec![]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let signal = |name: &str, dimensions| TemplateSignal {
+            name: name.to_string(),
+            dimensions,
+        };
+        assert_eq!(
+            templates.unwrap(),
+            vec![
+                TemplateInfo {
+                    name: "Multiplier".to_string(),
+                    params: vec![],
+                    inputs: vec![signal("a", 0), signal("b", 0)],
+                    outputs: vec![signal("c", 0)],
+                },
+                TemplateInfo {
+                    name: "Sum".to_string(),
+                    params: vec!["n".to_string()],
+                    inputs: vec![signal("in", 1)],
+                    outputs: vec![signal("out", 0)],
+                },
+            ]
+        );
+    }
 }
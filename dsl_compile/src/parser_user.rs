@@ -3,9 +3,16 @@ use crate::input_user::Input;
 use crate::CIRCOM_VERSION;
 use program_structure::error_definition::Report;
 use program_structure::program_archive::ProgramArchive;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 pub fn parse_project(input_info: &Input) -> Result<ProgramArchive> {
     let initial_file = input_info.input_file().to_string();
+    check_include_depth(
+        Path::new(&initial_file),
+        &input_info.link_libraries,
+        input_info.max_include_depth(),
+    )?;
     let result_program_archive = parser::run_parser(
         initial_file,
         CIRCOM_VERSION,
@@ -24,3 +31,160 @@ pub fn parse_project(input_info: &Input) -> Result<ProgramArchive> {
         }
     }
 }
+
+/// Walk the include graph before handing it to circom, so an overly deep include chain
+/// fails with the chain itself. Like circom, every file is only included once, so a file
+/// that was already reached, even one that includes the current file, is skipped.
+/// Includes that can't be resolved or parsed are left for circom to report.
+fn check_include_depth(
+    initial_file: &Path,
+    link_libraries: &[PathBuf],
+    max_include_depth: usize,
+) -> Result<()> {
+    struct Walker<'a> {
+        link_libraries: &'a [PathBuf],
+        max_include_depth: usize,
+        stack: Vec<PathBuf>,
+        visited: HashSet<PathBuf>,
+    }
+
+    impl Walker<'_> {
+        fn chain(&self, last: &Path) -> String {
+            self.stack
+                .iter()
+                .map(|p| p.as_path())
+                .chain(std::iter::once(last))
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        }
+
+        fn resolve(&self, current: &Path, include: &str) -> Option<PathBuf> {
+            current
+                .parent()
+                .into_iter()
+                .map(|dir| dir.join(include))
+                .chain(self.link_libraries.iter().map(|dir| dir.join(include)))
+                .find(|p| p.is_file())
+                .and_then(|p| p.canonicalize().ok())
+        }
+
+        fn visit(&mut self, file: PathBuf) -> Result<()> {
+            if !self.visited.insert(file.clone()) {
+                return Ok(());
+            }
+            if self.stack.len() > self.max_include_depth {
+                bail!(DslError::IncludeDepthExceeded(self.chain(&file)));
+            }
+
+            let src = match std::fs::read_to_string(&file) {
+                Ok(src) => src,
+                Err(_) => return Ok(()),
+            };
+            self.stack.push(file.clone());
+            for include in find_includes(&src) {
+                if let Some(next) = self.resolve(&file, &include) {
+                    self.visit(next)?;
+                }
+            }
+            self.stack.pop();
+            Ok(())
+        }
+    }
+
+    let initial_file = match initial_file.canonicalize() {
+        Ok(f) => f,
+        Err(_) => return Ok(()),
+    };
+    let mut walker = Walker {
+        link_libraries,
+        max_include_depth,
+        stack: vec![],
+        visited: HashSet::new(),
+    };
+    walker.visit(initial_file)
+}
+
+/// The paths of the `include "..."` statements, read with circom's own grammar.
+fn find_includes(src: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    parser::lang::ParseAstParser::new()
+        .parse(0, &mut errors, &strip_comments(src))
+        .map(|ast| ast.includes)
+        .unwrap_or_default()
+}
+
+/// Blank out the comments, which circom removes before running its grammar.
+fn strip_comments(src: &str) -> String {
+    let mut out = String::with_capacity(src.len());
+    let mut rest = src;
+    while let Some(c) = rest.chars().next() {
+        if let Some(r) = rest.strip_prefix("//") {
+            rest = r.find('\n').map_or("", |i| &r[i..]);
+            out.push(' ');
+        } else if let Some(r) = rest.strip_prefix("/*") {
+            let end = r.find("*/").map_or(r.len(), |i| i + 2);
+            out.extend(r[..end].chars().filter(|c| *c == '\n'));
+            out.push(' ');
+            rest = r.get(end..).unwrap_or("");
+        } else if c == '"' {
+            let end = rest[1..].find('"').map_or(rest.len(), |i| i + 2);
+            out.push_str(&rest[..end]);
+            rest = &rest[end..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_includes() {
+        let src = r#"
+            pragma circom 2.0.0;
+            include "a.circom";
+            // include "commented.circom";
+            /* include "block.circom"; */
+            include   "../lib/b.circom" ;
+            template noinclude() {
+                log("include // /* ");
+            }
+        "#;
+        assert_eq!(find_includes(src), vec!["a.circom", "../lib/b.circom"]);
+    }
+
+    #[test]
+    fn test_include_depth() {
+        let dir = std::env::temp_dir().join(format!("eigen_dsl_include_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.circom"), "include \"b.circom\";\n").unwrap();
+        std::fs::write(dir.join("b.circom"), "include \"a.circom\";\n").unwrap();
+        std::fs::write(dir.join("c.circom"), "include \"d.circom\";\n").unwrap();
+        std::fs::write(dir.join("d.circom"), "include \"e.circom\";\n").unwrap();
+        std::fs::write(dir.join("e.circom"), "pragma circom 2.0.0;\n").unwrap();
+
+        // circom only includes a file once, so files including each other are fine
+        let circular = check_include_depth(&dir.join("a.circom"), &[], 64);
+        let too_deep = check_include_depth(&dir.join("c.circom"), &[], 1);
+        let deep_enough = check_include_depth(&dir.join("c.circom"), &[], 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(circular.is_ok());
+        let err = too_deep.unwrap_err();
+        match err.downcast_ref::<DslError>() {
+            Some(DslError::IncludeDepthExceeded(chain)) => {
+                let chain = chain.split(" -> ").collect::<Vec<_>>();
+                assert_eq!(chain.len(), 3);
+                assert!(chain[0].ends_with("c.circom"));
+                assert!(chain[2].ends_with("e.circom"));
+            }
+            _ => panic!("unexpected error: {}", err),
+        }
+        assert!(deep_enough.is_ok());
+    }
+}
@@ -1,6 +1,6 @@
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 use crate::bellman_ce::{
-    groth16::{Parameters, Proof, VerifyingKey},
+    groth16::{Parameters, PreparedVerifyingKey, Proof, VerifyingKey},
     pairing::{
         bls12_381::{Bls12, Fr as Fr_bls12381},
         bn256::{Bn256, Fr},
@@ -12,8 +12,8 @@ use algebraic::{
     bellman_ce::Engine,
     circom_circuit::CircomCircuit,
     reader::load_r1cs,
-    witness::{load_input_for_witness, WitnessCalculator},
-    Field, PrimeField,
+    witness::{calculate_witness_element, load_input_for_witness, WitnessCalculator},
+    PrimeField,
 };
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use algebraic_gpu::{
@@ -27,8 +27,11 @@ use anyhow::{anyhow, bail, Result};
 use bellperson::{gpu, groth16::*};
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use blstrs::{Bls12, Scalar};
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use group::WnafGroup;
+#[cfg(any(feature = "cuda", feature = "opencl"))]
 use num_traits::Zero;
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use pairing::{Engine, MultiMillerLoop};
@@ -37,6 +40,10 @@ use rand_new as rand;
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 use rand_old as rand;
 use regex::Regex;
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use serde::{Deserialize, Serialize};
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use std::io::{Read, Write};
 
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 pub fn groth16_setup(
@@ -47,13 +54,13 @@ pub fn groth16_setup(
     to_hex: bool,
 ) -> Result<()> {
     let mut rng = rand::thread_rng();
-    match curve_type {
-        "BN128" => {
+    match normalize_curve_name(curve_type)? {
+        "bn128" => {
             let circuit = create_circuit_from_file::<Bn256>(circuit_file, None);
             let (pk, vk) = Groth16::circuit_specific_setup(circuit, &mut rng)?;
             write_pk_vk_to_files(curve_type, pk, vk, pk_file, vk_file, to_hex)?
         }
-        "BLS12381" => {
+        "bls12381" => {
             let circuit = create_circuit_from_file::<Bls12>(circuit_file, None);
             let (pk, vk) = Groth16::circuit_specific_setup(circuit, &mut rng)?;
             write_pk_vk_to_files(curve_type, pk, vk, pk_file, vk_file, to_hex)?
@@ -75,13 +82,13 @@ pub enum SetupResult {
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 pub fn groth16_setup_inplace(curve_type: &str, circuit_file: &str) -> Result<SetupResult> {
     let mut rng = rand::thread_rng();
-    let result = match curve_type {
-        "BN128" => {
+    let result = match normalize_curve_name(curve_type)? {
+        "bn128" => {
             let circuit = create_circuit_from_file::<Bn256>(circuit_file, None);
             let (pk, vk) = Groth16::circuit_specific_setup(circuit.clone(), &mut rng)?;
             SetupResult::BN128(circuit, pk, vk)
         }
-        "BLS12381" => {
+        "bls12381" => {
             let circuit = create_circuit_from_file::<Bls12>(circuit_file, None);
             let (pk, vk) = Groth16::circuit_specific_setup(circuit.clone(), &mut rng)?;
             SetupResult::BLS12381(circuit, pk, vk)
@@ -102,8 +109,8 @@ pub fn groth16_setup(
     to_hex: bool,
 ) -> Result<()> {
     let mut rng = rand::thread_rng();
-    match curve_type {
-        "BLS12381" => {
+    match normalize_curve_name(curve_type)? {
+        "bls12381" => {
             let circuit = create_circuit_from_file::<Scalar>(circuit_file, None);
             let (pk, vk): (Parameters<Bls12>, VerifyingKey<Bls12>) =
                 Groth16::circuit_specific_setup(circuit, &mut rng)?;
@@ -129,8 +136,8 @@ pub enum SetupResult {
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 pub fn groth16_setup_inplace(curve_type: &str, circuit_file: &str) -> Result<SetupResult> {
     let mut rng = rand::thread_rng();
-    let result = match curve_type {
-        "BLS12381" => {
+    let result = match normalize_curve_name(curve_type)? {
+        "bls12381" => {
             let circuit = create_circuit_from_file::<Scalar>(circuit_file, None);
             let (pk, vk): (Parameters<Bls12>, VerifyingKey<Bls12>) =
                 Groth16::circuit_specific_setup(circuit.clone(), &mut rng)?;
@@ -160,19 +167,13 @@ pub fn groth16_prove(
     let mut wtns = WitnessCalculator::from_file(wtns_file)?;
     let inputs = load_input_for_witness(input_file);
     let w = wtns.calculate_witness(inputs, false)?;
-    match curve_type {
-        "BN128" => {
+    match normalize_curve_name(curve_type)? {
+        "bn128" => {
             let pk: Parameters<Bn256> = read_pk_from_file(pk_file, false)?;
             let w = w
                 .iter()
-                .map(|wi| {
-                    if wi.is_zero() {
-                        Fr::zero()
-                    } else {
-                        Fr::from_str(&wi.to_string()).unwrap()
-                    }
-                })
-                .collect::<Vec<_>>();
+                .map(calculate_witness_element::<Bn256>)
+                .collect::<Result<Vec<_>>>()?;
             let circuit = create_circuit_from_file::<Bn256>(circuit_file, Some(w));
             let proof = Groth16::prove(&pk, circuit.clone(), &mut rng)?;
             let proof_json = serialize_proof(&proof, curve_type, to_hex)?;
@@ -180,18 +181,12 @@ pub fn groth16_prove(
             let input_json = circuit.get_public_inputs_json();
             std::fs::write(public_input_file, input_json)?;
         }
-        "BLS12381" => {
+        "bls12381" => {
             let pk: Parameters<Bls12> = read_pk_from_file(pk_file, false)?;
             let w = w
                 .iter()
-                .map(|wi| {
-                    if wi.is_zero() {
-                        Fr_bls12381::zero()
-                    } else {
-                        Fr_bls12381::from_str(&wi.to_string()).unwrap()
-                    }
-                })
-                .collect::<Vec<_>>();
+                .map(calculate_witness_element::<Bls12>)
+                .collect::<Result<Vec<_>>>()?;
             let circuit = create_circuit_from_file::<Bls12>(circuit_file, Some(w));
             let proof = Groth16::prove(&pk, circuit.clone(), &mut rng)?;
             let proof_json = serialize_proof(&proof, curve_type, to_hex)?;
@@ -248,8 +243,8 @@ pub fn groth16_prove(
     let mut wtns = WitnessCalculator::from_file(wtns_file)?;
     let inputs = load_input_for_witness(input_file);
     let w = wtns.calculate_witness(inputs, false)?;
-    match curve_type {
-        "BLS12381" => {
+    match normalize_curve_name(curve_type)? {
+        "bls12381" => {
             let pk: Parameters<Bls12> = read_pk_from_file(pk_file, false)?;
             let w = w
                 .iter()
@@ -309,8 +304,8 @@ pub fn groth16_verify(
     public_input_file: &str,
     proof_file: &str,
 ) -> Result<()> {
-    match curve_type {
-        "BN128" => {
+    match normalize_curve_name(curve_type)? {
+        "bn128" => {
             let vk = read_vk_from_file(vk_file)?;
             let inputs = read_public_input_from_file::<Fr>(public_input_file)?;
             let proof = read_proof_from_file(proof_file)?;
@@ -323,7 +318,7 @@ pub fn groth16_verify(
             }
         }
 
-        "BLS12381" => {
+        "bls12381" => {
             let vk = read_vk_from_file(vk_file)?;
             let inputs = read_public_input_from_file::<Fr_bls12381>(public_input_file)?;
             let proof = read_proof_from_file(proof_file)?;
@@ -344,6 +339,42 @@ pub fn groth16_verify(
     Ok(())
 }
 
+/// Verify the snarkjs style `vk_json` and `proof_json` against public inputs given as
+/// `0x` prefixed bytes32 words, the way they are laid out in EVM calldata.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn verify_from_hex(
+    curve_type: &str,
+    vk_json: &str,
+    proof_json: &str,
+    public_hex: &[String],
+) -> Result<bool> {
+    fn verify<E: Engine + Parser>(
+        vk_json: &str,
+        proof_json: &str,
+        public_hex: &[String],
+    ) -> Result<bool> {
+        let inputs = public_hex
+            .iter()
+            .map(|h| match h.strip_prefix("0x") {
+                // a field element as it appears in EVM calldata
+                Some(digits) if digits.len() == 64 => {
+                    Ok(render_hex_to_scalar::<E::Fr>(h, false, Endianness::Big)?)
+                }
+                _ => bail!("expect a 0x prefixed bytes32, got {}", h),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let vk = to_verification_key::<E>(vk_json)?;
+        let proof = to_proof::<E>(proof_json)?;
+        Groth16::<_, CircomCircuit<E>>::verify_with_processed_vk(&vk, &inputs, &proof)
+    }
+
+    match normalize_curve_name(curve_type)? {
+        "bn128" => verify::<Bn256>(vk_json, proof_json, public_hex),
+        "bls12381" => verify::<Bls12>(vk_json, proof_json, public_hex),
+        _ => bail!("Unknown curve type: {}", curve_type),
+    }
+}
+
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 pub fn groth16_verify_inplace<E: Engine + crate::json_utils::Parser>(
     vk: VerifyingKey<E>,
@@ -369,8 +400,8 @@ pub fn groth16_verify(
     public_input_file: &str,
     proof_file: &str,
 ) -> Result<()> {
-    match curve_type {
-        "BLS12381" => {
+    match normalize_curve_name(curve_type)? {
+        "bls12381" => {
             let vk: VerifyingKey<Bls12> = read_vk_from_file(vk_file)?;
             let inputs: Vec<Scalar> = read_public_input_from_file(public_input_file)?;
             let proof = read_proof_from_file(proof_file)?;
@@ -390,12 +421,36 @@ pub fn groth16_verify(
     Ok(())
 }
 
-// Acknowledgement: The Solidity verifier template was modified from ZoKrates implementation.
 pub fn generate_verifier(vk_file_path: &str, sol_file_path: &str) -> Result<()> {
     let json_data = std::fs::read_to_string(vk_file_path)?;
     let vk_file: VerifyingKeyFile =
         serde_json::from_str(&json_data).expect("Error during deserialization of the JSON data");
 
+    match std::fs::write(sol_file_path, solidity_verifier(vk_file)) {
+        Ok(()) => println!("Generate solidity verifier successfully!"),
+        Err(e) => {
+            bail!("write sol file failed, {:?}", e)
+        }
+    }
+    Ok(())
+}
+
+/// The `Verifier.sol` of `vk`, with its points hardcoded. Only BN254 has the EVM
+/// precompiles the contract calls, other curves are rejected.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn export_solidity_verifier<P: Parser>(vk: &VerifyingKey<P>) -> Result<String> {
+    if P::curve_name() != "bn128" {
+        bail!(
+            "no EVM precompile for {}, only bn128 verifiers can be exported",
+            P::curve_name()
+        );
+    }
+    let vk_file: VerifyingKeyFile = serde_json::from_str(&serialize_vk(vk, "bn128", true)?)?;
+    Ok(solidity_verifier(vk_file))
+}
+
+// Acknowledgement: The Solidity verifier template was modified from ZoKrates implementation.
+fn solidity_verifier(vk_file: VerifyingKeyFile) -> String {
     let vk_alpha = vk_file.alpha_g1.to_string();
     let vk_beta = vk_file.beta_g2.to_string();
     let vk_gamma = vk_file.gamma_g2.to_string();
@@ -490,16 +545,7 @@ pub fn generate_verifier(vk_file_path: &str, sol_file_path: &str) -> Result<()>
     let re = Regex::new(r"(?P<v>0[xX][0-9a-fA-F]{64})").unwrap();
     template_text = re.replace_all(&template_text, "uint256($v)").to_string();
 
-    match std::fs::write(
-        sol_file_path,
-        format!("{}{}", solidity_pairing_lib_sans_bn256g2, template_text),
-    ) {
-        Ok(()) => println!("Generate solidity verifier successfully!"),
-        Err(e) => {
-            bail!("write sol file failed, {:?}", e)
-        }
-    }
-    Ok(())
+    format!("{}{}", solidity_pairing_lib_sans_bn256g2, template_text)
 }
 
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
@@ -522,13 +568,7 @@ pub fn create_circuit_add_witness<E: Engine>(
 ) -> CircomCircuit<E> {
     let witness: Vec<E::Fr> = witness
         .iter()
-        .map(|wi| {
-            if wi.is_zero() {
-                E::Fr::zero()
-            } else {
-                E::Fr::from_str(&wi.to_string()).unwrap()
-            }
-        })
+        .map(|wi| calculate_witness_element::<E>(wi).unwrap())
         .collect::<Vec<_>>();
     circuit.witness = Some(witness);
     circuit.wire_mapping = None;
@@ -594,23 +634,138 @@ where
 
 pub fn read_vk_from_file<P: Parser>(file_path: &str) -> Result<VerifyingKey<P>> {
     let json_data = std::fs::read_to_string(file_path)?;
-    Ok(to_verification_key::<P>(&json_data))
+    Ok(to_verification_key::<P>(&json_data)?)
 }
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub enum PreparedVk {
+    BN128(PreparedVerifyingKey<Bn256>),
+    BLS12381(PreparedVerifyingKey<Bls12>),
+}
+
+/// Read a snarkjs `verification_key.json` and prepare it for verification.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn load_prepared_vk_json<P: Parser>(file_path: &str) -> Result<PreparedVerifyingKey<P>> {
+    let json_data =
+        std::fs::read_to_string(file_path).map_err(|e| anyhow!("Open {}, {:?}", file_path, e))?;
+    crate::groth16::deserialize_pvk(&json_data)
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn load_prepared_vk_json_for_curve(curve_type: &str, file_path: &str) -> Result<PreparedVk> {
+    let pvk = match normalize_curve_name(curve_type)? {
+        "bn128" => PreparedVk::BN128(load_prepared_vk_json(file_path)?),
+        "bls12381" => PreparedVk::BLS12381(load_prepared_vk_json(file_path)?),
+        _ => {
+            bail!(format!("Unknown curve type: {}", curve_type))
+        }
+    };
+    Ok(pvk)
+}
+
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 fn read_public_input_from_file<T: PrimeField>(file_path: &str) -> Result<Vec<T>> {
     let json_data = std::fs::read_to_string(file_path)?;
-    Ok(to_public_input::<T>(&json_data))
+    Ok(to_public_input::<T>(&json_data)?)
 }
 
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 fn read_public_input_from_file(file_path: &str) -> Result<Vec<Scalar>> {
     let json_data = std::fs::read_to_string(file_path)?;
-    Ok(to_public_input(&json_data))
+    to_public_input(&json_data)
 }
 
 fn read_proof_from_file<P: Parser>(file_path: &str) -> Result<Proof<P>> {
     let json_data = std::fs::read_to_string(file_path)?;
-    Ok(to_proof::<P>(&json_data))
+    Ok(to_proof::<P>(&json_data)?)
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+const SETUP_ARCHIVE_MAGIC: &[u8; 4] = b"egsa";
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+const SETUP_ARCHIVE_VERSION: u32 = 1;
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+#[derive(Debug, Serialize, Deserialize)]
+struct SetupManifest {
+    protocol: String,
+    curve: String,
+    n_public: usize,
+}
+
+/// Bundle the proving key and its verifying key into a single file.
+///
+/// Layout: magic, version, then the manifest JSON, the vk JSON and the binary pk,
+/// each prefixed by its u64 little-endian length.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn save_setup_archive<P: Parser>(
+    path: &str,
+    params: &Parameters<P>,
+    curve_type: &str,
+) -> Result<()> {
+    let manifest = SetupManifest {
+        protocol: "groth16".to_string(),
+        curve: normalize_curve_name(curve_type)?.to_string(),
+        n_public: params.vk.ic.len() - 1,
+    };
+    let manifest = serde_json::to_vec(&manifest)?;
+    let vk_json = serialize_vk(&params.vk, curve_type, false)?;
+    let mut pk = vec![];
+    params.write(&mut pk)?;
+
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+    writer.write_all(SETUP_ARCHIVE_MAGIC)?;
+    writer.write_u32::<LittleEndian>(SETUP_ARCHIVE_VERSION)?;
+    for section in [&manifest[..], vk_json.as_bytes(), &pk[..]] {
+        writer.write_u64::<LittleEndian>(section.len() as u64)?;
+        writer.write_all(section)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Load an archive written by `save_setup_archive`, checking it was made for `curve_type`
+/// and that the bundled vk is the one of the proving key.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn load_setup_archive<P: Parser>(path: &str, curve_type: &str) -> Result<Parameters<P>> {
+    let file = std::fs::File::open(path).map_err(|e| anyhow!("Open {}, {:?}", path, e))?;
+    let mut reader = std::io::BufReader::new(file);
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != SETUP_ARCHIVE_MAGIC {
+        bail!("{} is not a setup archive", path);
+    }
+    let version = reader.read_u32::<LittleEndian>()?;
+    if version != SETUP_ARCHIVE_VERSION {
+        bail!("Unsupported setup archive version: {}", version);
+    }
+    let mut sections = vec![];
+    for _ in 0..3 {
+        let len = reader.read_u64::<LittleEndian>()?;
+        let mut section = vec![];
+        (&mut reader).take(len).read_to_end(&mut section)?;
+        if section.len() as u64 != len {
+            bail!("{} is truncated", path);
+        }
+        sections.push(section);
+    }
+
+    let manifest: SetupManifest = serde_json::from_slice(&sections[0])?;
+    let curve = normalize_curve_name(curve_type)?;
+    if manifest.curve != curve {
+        bail!(
+            "Setup archive is for curve {}, expected {}",
+            manifest.curve,
+            curve
+        );
+    }
+    let params = Parameters::<P>::read(&sections[2][..], true)?;
+    let vk_json = std::str::from_utf8(&sections[1])?;
+    if serialize_vk(&params.vk, curve_type, false)? != vk_json
+        || manifest.n_public + 1 != params.vk.ic.len()
+    {
+        bail!("Setup archive vk does not match its proving key");
+    }
+    Ok(params)
 }
 
 fn write_pk_vk_to_files<P: Parser>(
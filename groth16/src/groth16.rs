@@ -1,11 +1,32 @@
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
-use crate::bellman_ce::{groth16::*, Circuit};
+use crate::bellman_ce::{groth16::*, Circuit, ConstraintSystem, SynthesisError};
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use crate::json_utils::{serialize_vk, to_verification_key, CurveCoordinates, Parser};
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use algebraic::{
+    circom_circuit::{CircomCircuit, R1CS},
+    errors::EigenError,
+    witness::WitnessCalculator,
+};
 use anyhow::Result;
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
-use franklin_crypto::bellman::pairing::Engine;
+use anyhow::{anyhow, bail};
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use franklin_crypto::bellman::pairing::{
+    ff::{Field, PrimeField, PrimeFieldRepr},
+    CurveAffine, CurveProjective, Engine,
+};
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use num_bigint::BigInt;
 #[allow(unused_imports)]
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 use rand_old::{self as rand, Rng};
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use rand_old::{ChaChaRng, SeedableRng};
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use std::io::{BufReader, BufWriter, Read, Write};
 
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use bellperson::{gpu, groth16::*, Circuit};
@@ -56,16 +77,44 @@ where
         Ok(result)
     }
 
+    /// Prepare `circuit_vk` for verification, to be done once and reused across proofs.
+    pub fn process_vk(circuit_vk: &VerifyingKey<E>) -> PreparedVerifyingKey<E> {
+        prepare_verifying_key(circuit_vk)
+    }
+
+    pub fn verify_with_prepared_vk(
+        circuit_pvk: &PreparedVerifyingKey<E>,
+        public_input: &[E::Fr],
+        proof: &Proof<E>,
+    ) -> Result<bool> {
+        let result = verify_proof(circuit_pvk, proof, public_input)?;
+
+        Ok(result)
+    }
+
+    /// Same as `verify_with_prepared_vk`, preparing `circuit_vk` on every call.
     pub fn verify_with_processed_vk(
         circuit_vk: &VerifyingKey<E>,
         public_input: &[E::Fr],
         proof: &Proof<E>,
     ) -> Result<bool> {
-        let circuit_pvk = prepare_verifying_key(circuit_vk);
-        let result = verify_proof(&circuit_pvk, proof, public_input)?;
+        let circuit_pvk = Self::process_vk(circuit_vk);
+        Self::verify_with_prepared_vk(&circuit_pvk, public_input, proof)
+    }
+}
 
-        Ok(result)
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+fn check_public_input_length<E: Engine>(
+    circuit_vk: &VerifyingKey<E>,
+    public_input: &[E::Fr],
+) -> std::result::Result<(), EigenError> {
+    if public_input.len() + 1 != circuit_vk.ic.len() {
+        return Err(EigenError::PublicInputLength {
+            expected: circuit_vk.ic.len().saturating_sub(1),
+            got: public_input.len(),
+        });
     }
+    Ok(())
 }
 
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
@@ -73,6 +122,56 @@ pub struct Groth16<E: Engine, C: Circuit<E>> {
     _engine: PhantomData<E>,
     _circuit: PhantomData<C>,
 }
+
+/// Per-constraint proving cost measured on the current machine, see `Groth16::calibrate`.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+#[derive(Clone, Copy, Debug)]
+pub struct Calibration {
+    pub per_constraint: std::time::Duration,
+}
+/// A step of `Groth16::prove_with_progress`.
+///
+/// bellman computes the H polynomial FFTs and the A, B, C, H and L multiexps in a single
+/// call, so they are one step here.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProvePhase {
+    /// the circuit is synthesized, assigning the witness
+    WitnessAssignment,
+    /// the FFTs and multiexps
+    FftAndMultiexp,
+    /// the proof is complete
+    Done,
+}
+
+// reports the start and end of the synthesis, the only part of the prover observable from
+// outside bellman
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+struct ProgressCircuit<'a, C, F> {
+    inner: C,
+    progress: &'a F,
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+impl<E: Engine, C: Circuit<E>, F: Fn(ProvePhase)> Circuit<E> for ProgressCircuit<'_, C, F> {
+    fn synthesize<CS: ConstraintSystem<E>>(self, cs: &mut CS) -> Result<(), SynthesisError> {
+        (self.progress)(ProvePhase::WitnessAssignment);
+        self.inner.synthesize(cs)?;
+        (self.progress)(ProvePhase::FftAndMultiexp);
+        Ok(())
+    }
+}
+
+// rand 0.4's ChaChaRng is ChaCha20, seeded with the key as little endian words
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+fn seeded_rng(seed: [u8; 32]) -> ChaChaRng {
+    let seed = seed
+        .chunks(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect::<Vec<_>>();
+    ChaChaRng::from_seed(&seed[..])
+}
+
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 impl<E: Engine, C: Circuit<E>> Groth16<E, C> {
     pub fn circuit_specific_setup<R: Rng>(
@@ -85,6 +184,18 @@ impl<E: Engine, C: Circuit<E>> Groth16<E, C> {
         Ok((pk, vk))
     }
 
+    /// Setup with the randomness drawn from a ChaCha stream seeded by `seed`, so the same
+    /// seed always gives the same keys.
+    ///
+    /// **Never use this in production**: anyone who knows the seed can recompute the toxic
+    /// waste and forge proofs. It only exists to generate stable test fixtures.
+    pub fn setup_deterministic(
+        circuit: C,
+        seed: [u8; 32],
+    ) -> Result<(Parameters<E>, VerifyingKey<E>)> {
+        Self::circuit_specific_setup(circuit, &mut seeded_rng(seed))
+    }
+
     pub fn prove<R: Rng>(
         circuit_pk: &Parameters<E>,
         input_and_witness: C,
@@ -95,18 +206,328 @@ impl<E: Engine, C: Circuit<E>> Groth16<E, C> {
         Ok(result)
     }
 
+    /// Prove with the blinding factors `r` and `s` drawn from a ChaCha20 stream seeded by
+    /// `seed`, so the same seed and witness always give the same proof.
+    ///
+    /// Groth16 proofs are randomized on purpose, reusing the randomness across different
+    /// witnesses can leak them. Only use this for reproducible tests and golden files.
+    pub fn prove_deterministic(
+        circuit_pk: &Parameters<E>,
+        input_and_witness: C,
+        seed: [u8; 32],
+    ) -> Result<Proof<E>> {
+        Self::prove(circuit_pk, input_and_witness, &mut seeded_rng(seed))
+    }
+
+    /// Same as `prove`, calling `progress` as each `ProvePhase` begins.
+    pub fn prove_with_progress<R: Rng, F: Fn(ProvePhase)>(
+        circuit_pk: &Parameters<E>,
+        input_and_witness: C,
+        rng: &mut R,
+        progress: F,
+    ) -> Result<Proof<E>> {
+        let circuit = ProgressCircuit {
+            inner: input_and_witness,
+            progress: &progress,
+        };
+        let result = create_random_proof::<E, _, _, _>(circuit, circuit_pk, rng)?;
+        progress(ProvePhase::Done);
+
+        Ok(result)
+    }
+
+    /// Prepare `circuit_vk` for verification, to be done once and reused across proofs.
+    pub fn process_vk(circuit_vk: &VerifyingKey<E>) -> PreparedVerifyingKey<E> {
+        prepare_verifying_key(circuit_vk)
+    }
+
+    /// The prepared key doesn't expose its IC, so a wrong number of public inputs only
+    /// fails as bellman's `MalformedVerifyingKey`, `verify_with_processed_vk` reports it.
+    pub fn verify_with_prepared_vk(
+        circuit_pvk: &PreparedVerifyingKey<E>,
+        public_input: &[E::Fr],
+        proof: &Proof<E>,
+    ) -> Result<bool> {
+        let result = verify_proof(circuit_pvk, proof, public_input)?;
+
+        Ok(result)
+    }
+
+    /// Same as `verify_with_prepared_vk`, preparing `circuit_vk` on every call, failing
+    /// with `EigenError::PublicInputLength` unless `public_input` matches its IC.
     pub fn verify_with_processed_vk(
         circuit_vk: &VerifyingKey<E>,
         public_input: &[E::Fr],
         proof: &Proof<E>,
     ) -> Result<bool> {
-        let circuit_pvk = prepare_verifying_key(circuit_vk);
-        let result = verify_proof(&circuit_pvk, proof, public_input)?;
+        check_public_input_length(circuit_vk, public_input)?;
+        let circuit_pvk = Self::process_vk(circuit_vk);
+        Self::verify_with_prepared_vk(&circuit_pvk, public_input, proof)
+    }
 
-        Ok(result)
+    /// Verify `proofs` against their `public_inputs` with a single multi-pairing.
+    ///
+    /// Each proof's equation `e(A, B) = e(alpha, beta) e(IC, gamma) e(C, delta)` is raised
+    /// to a random power drawn from `rng` and all of them multiplied together, so one bad
+    /// proof fails the whole batch but with negligible probability. That costs `n + 3`
+    /// Miller loops and one final exponentiation instead of `n` full verifications.
+    ///
+    /// An empty batch is an error rather than vacuously valid.
+    pub fn batch_verify_with_processed_vk<R: Rng>(
+        circuit_vk: &VerifyingKey<E>,
+        public_inputs: &[Vec<E::Fr>],
+        proofs: &[Proof<E>],
+        rng: &mut R,
+    ) -> Result<bool> {
+        if public_inputs.len() != proofs.len() {
+            bail!(
+                "{} public input sets for {} proofs",
+                public_inputs.len(),
+                proofs.len()
+            );
+        }
+        if proofs.is_empty() {
+            bail!("no proofs to batch verify");
+        }
+        let mut r_sum = E::Fr::zero();
+        let mut acc_ic = E::G1::zero();
+        let mut acc_c = E::G1::zero();
+        let mut pairs = Vec::with_capacity(proofs.len() + 3);
+        for (inputs, proof) in public_inputs.iter().zip(proofs) {
+            check_public_input_length(circuit_vk, inputs)?;
+            let r: E::Fr = rng.gen();
+            r_sum.add_assign(&r);
+
+            let mut ic = circuit_vk.ic[0].into_projective();
+            for (x, base) in inputs.iter().zip(circuit_vk.ic.iter().skip(1)) {
+                ic.add_assign(&base.mul(x.into_repr()));
+            }
+            ic.mul_assign(r.into_repr());
+            acc_ic.add_assign(&ic);
+            acc_c.add_assign(&proof.c.mul(r.into_repr()));
+            pairs.push((
+                proof.a.mul(r.into_repr()).into_affine().prepare(),
+                proof.b.prepare(),
+            ));
+        }
+
+        let mut alpha = circuit_vk.alpha_g1.mul(r_sum.into_repr());
+        alpha.negate();
+        acc_ic.negate();
+        acc_c.negate();
+        pairs.push((alpha.into_affine().prepare(), circuit_vk.beta_g2.prepare()));
+        pairs.push((
+            acc_ic.into_affine().prepare(),
+            circuit_vk.gamma_g2.prepare(),
+        ));
+        pairs.push((acc_c.into_affine().prepare(), circuit_vk.delta_g2.prepare()));
+
+        let refs = pairs.iter().map(|(a, b)| (a, b)).collect::<Vec<_>>();
+        let result = E::final_exponentiation(&E::miller_loop(refs.iter()));
+        Ok(result == Some(E::Fqk::one()))
+    }
+
+    /// Time a few curve multiplications to get the per-constraint cost of proving here.
+    ///
+    /// The prover does about four G1 and one G2 multiplication per constraint, the multiexp
+    /// makes them cheaper than this naive count, so the figure is a rough upper bound.
+    pub fn calibrate() -> Calibration {
+        const SAMPLES: u32 = 16;
+        let mut rng = rand::thread_rng();
+        let scalars = (0..SAMPLES).map(|_| rng.gen::<E::Fr>()).collect::<Vec<_>>();
+        let start = std::time::Instant::now();
+        for s in scalars.iter() {
+            for _ in 0..4 {
+                std::hint::black_box(E::G1Affine::one().mul(s.into_repr()));
+            }
+            std::hint::black_box(E::G2Affine::one().mul(s.into_repr()));
+        }
+        Calibration {
+            per_constraint: start.elapsed() / SAMPLES,
+        }
+    }
+
+    /// Order of magnitude estimate of the time to prove a circuit of `constraint_count`
+    /// constraints, it ignores witness generation, the FFTs and the multiexp speedup.
+    pub fn estimate_prove_time(
+        constraint_count: usize,
+        calibration: &Calibration,
+    ) -> std::time::Duration {
+        calibration.per_constraint.mul_f64(constraint_count as f64)
+    }
+
+    /// `IC[0] + IC[1] + ... + IC[n]` of `circuit_vk`, a value some reference tools print to
+    /// cross-check a vk against another implementation's.
+    pub fn debug_ic_sum(circuit_vk: &VerifyingKey<E>) -> E::G1 {
+        let mut sum = E::G1::zero();
+        for ic in circuit_vk.ic.iter() {
+            sum.add_assign_mixed(ic);
+        }
+        sum
     }
 }
 
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+impl<E: CurveCoordinates, C: Circuit<E>> Groth16<E, C> {
+    /// Same as `verify_with_processed_vk`, without data dependent early exits: the public
+    /// inputs are always fully accumulated, the whole multi-pairing is evaluated, and the two
+    /// target group elements are compared over their full encodings in constant time.
+    ///
+    /// The curve arithmetic underneath is bellman's, which makes no constant time promise of
+    /// its own, only the final comparison is guaranteed not to leak where the results differ.
+    pub fn verify_ct(
+        circuit_vk: &VerifyingKey<E>,
+        public_input: &[E::Fr],
+        proof: &Proof<E>,
+    ) -> Result<bool> {
+        use subtle::ConstantTimeEq;
+        check_public_input_length(circuit_vk, public_input)?;
+        let mut acc = circuit_vk.ic[0].into_projective();
+        for (input, ic) in public_input.iter().zip(circuit_vk.ic.iter().skip(1)) {
+            acc.add_assign(&ic.mul(input.into_repr()));
+        }
+
+        let mut neg_gamma_g2 = circuit_vk.gamma_g2;
+        neg_gamma_g2.negate();
+        let mut neg_delta_g2 = circuit_vk.delta_g2;
+        neg_delta_g2.negate();
+        let lhs = E::final_exponentiation(&E::miller_loop(
+            [
+                (&proof.a.prepare(), &proof.b.prepare()),
+                (&acc.into_affine().prepare(), &neg_gamma_g2.prepare()),
+                (&proof.c.prepare(), &neg_delta_g2.prepare()),
+            ]
+            .iter(),
+        ))
+        .ok_or_else(|| anyhow!("final exponentiation failed"))?;
+        let rhs = E::pairing(circuit_vk.alpha_g1, circuit_vk.beta_g2);
+
+        let (lhs, rhs) = (fqk_to_bytes::<E>(&lhs)?, fqk_to_bytes::<E>(&rhs)?);
+        Ok(bool::from(lhs.as_slice().ct_eq(rhs.as_slice())))
+    }
+}
+
+// the fixed size little-endian encoding of every `Fq` coefficient, tower order
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+fn fqk_to_bytes<E: CurveCoordinates>(e: &E::Fqk) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    for c in E::fqk_to_fq(e) {
+        c.into_repr().write_le(&mut bytes)?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+impl<E: Engine> Groth16<E, CircomCircuit<E>> {
+    /// Compute the witness of `inputs` with `wtns`, check it satisfies `r1cs` and prove it.
+    pub fn prove_from_inputs<R: Rng, I: IntoIterator<Item = (String, Vec<BigInt>)>>(
+        circuit_pk: &Parameters<E>,
+        wtns: &mut WitnessCalculator,
+        r1cs: &R1CS<E>,
+        inputs: I,
+        rng: &mut R,
+    ) -> Result<Proof<E>> {
+        let witness = wtns.calculate_witness_fr_parallel::<E, _>(inputs, true)?;
+        if witness.len() != r1cs.num_variables {
+            bail!(
+                "the witness has {} elements, the r1cs {} variables",
+                witness.len(),
+                r1cs.num_variables
+            );
+        }
+        if let Some(i) = r1cs.first_unsatisfied_constraint(&witness) {
+            bail!("the witness doesn't satisfy constraint {}", i);
+        }
+        let circuit = CircomCircuit {
+            r1cs: r1cs.clone(),
+            witness: Some(witness),
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        Self::prove(circuit_pk, circuit, rng)
+    }
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+const PROVING_KEY_MAGIC: &[u8; 4] = b"ezpk";
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+const PROVING_KEY_VERSION: u32 = 1;
+
+/// Write `pk` as magic, version, the curve name prefixed by its u32 little-endian length,
+/// then bellman's binary encoding of the parameters.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn write_proving_key<P: Parser, W: Write>(pk: &Parameters<P>, mut w: W) -> Result<()> {
+    let curve = P::curve_name().as_bytes();
+    w.write_all(PROVING_KEY_MAGIC)?;
+    w.write_u32::<LittleEndian>(PROVING_KEY_VERSION)?;
+    w.write_u32::<LittleEndian>(curve.len() as u32)?;
+    w.write_all(curve)?;
+    pk.write(&mut w)?;
+    w.flush()?;
+    Ok(())
+}
+
+/// Read a proving key written by `write_proving_key`, failing if it was written for
+/// another curve or by an unsupported version.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn read_proving_key<P: Parser, R: Read>(mut r: R) -> Result<Parameters<P>> {
+    let mut magic = [0u8; 4];
+    r.read_exact(&mut magic)?;
+    if &magic != PROVING_KEY_MAGIC {
+        bail!("not a proving key file");
+    }
+    let version = r.read_u32::<LittleEndian>()?;
+    if version != PROVING_KEY_VERSION {
+        bail!("Unsupported proving key version: {}", version);
+    }
+    let len = r.read_u32::<LittleEndian>()? as usize;
+    if len > 32 {
+        bail!("invalid proving key curve name length: {}", len);
+    }
+    let mut curve = vec![0u8; len];
+    r.read_exact(&mut curve)?;
+    if curve != P::curve_name().as_bytes() {
+        bail!(
+            "proving key is for curve {}, expected {}",
+            String::from_utf8_lossy(&curve),
+            P::curve_name()
+        );
+    }
+    Ok(Parameters::<P>::read(&mut r, false)?)
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn write_proving_key_to_file<P: Parser>(pk: &Parameters<P>, file_path: &str) -> Result<()> {
+    let file =
+        std::fs::File::create(file_path).map_err(|e| anyhow!("Create {}, {:?}", file_path, e))?;
+    write_proving_key(pk, BufWriter::new(file))
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn read_proving_key_from_file<P: Parser>(file_path: &str) -> Result<Parameters<P>> {
+    let file =
+        std::fs::File::open(file_path).map_err(|e| anyhow!("Open {}, {:?}", file_path, e))?;
+    read_proving_key(BufReader::new(file))
+}
+
+/// Serialize the prepared form of `vk` as JSON for `deserialize_pvk`.
+///
+/// bellman keeps the prepared G2 points of a `PreparedVerifyingKey` private, so what is
+/// written is `vk` itself, a snarkjs `verification_key.json` carrying the engine's curve
+/// name. A pvk is engine-specific, it only deserializes for the engine it was written for.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn serialize_pvk<P: Parser>(vk: &VerifyingKey<P>) -> Result<String> {
+    serialize_vk(vk, P::curve_name(), false)
+}
+
+/// Read what `serialize_pvk` wrote and prepare it, checking the points and that it was
+/// written for `P`.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn deserialize_pvk<P: Parser>(s: &str) -> Result<PreparedVerifyingKey<P>> {
+    let vk = to_verification_key::<P>(s)?;
+    Ok(prepare_verifying_key(&vk))
+}
+
 #[cfg(test)]
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 mod tests {
@@ -118,8 +539,14 @@ mod tests {
     use crate::api::create_circuit_add_witness;
     use crate::api::SetupResult;
     use crate::api::{groth16_prove_inplace, groth16_setup_inplace, groth16_verify_inplace};
+    use crate::api::{load_prepared_vk_json, load_prepared_vk_json_for_curve, PreparedVk};
+    use crate::api::{load_setup_archive, save_setup_archive, verify_from_hex};
     use crate::bellman_ce::bls12_381::Bls12;
     use crate::bellman_ce::bn256::{Bn256, Fr};
+    use crate::json_utils::{
+        extract_and_validate_public, parse_proof_bundle, render_scalar_to_str, serialize_input,
+        serialize_proof, serialize_proof_bundle, serialize_vk, to_public_input,
+    };
     use algebraic::circom_circuit::CircomCircuit;
     use algebraic::reader;
     use algebraic::witness::{load_input_for_witness, WitnessCalculator};
@@ -135,6 +562,25 @@ mod tests {
         "/test-vectors/mycircuit_bls12381.wasm"
     );
 
+    fn multiplier_proof() -> Result<(VerifyingKey<Bn256>, Vec<Fr>, Proof<Bn256>)> {
+        let circuit: CircomCircuit<Bn256> = CircomCircuit {
+            r1cs: reader::load_r1cs(CIRCUIT_FILE),
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        let mut rng = rand::thread_rng();
+        let (pk, vk) = Groth16::circuit_specific_setup(circuit.clone(), &mut rng)?;
+
+        let mut wtns = WitnessCalculator::from_file(WASM_FILE)?;
+        let inputs = load_input_for_witness(INPUT_FILE);
+        let w = wtns.calculate_witness(inputs, false)?;
+        let circuit = create_circuit_add_witness::<Bn256>(circuit, w);
+        let inputs = circuit.get_public_inputs().unwrap();
+        let proof = Groth16::prove(&pk, circuit, &mut rng)?;
+        Ok((vk, inputs, proof))
+    }
+
     #[test]
     fn groth16_proof() -> Result<()> {
         //1. SRS
@@ -179,9 +625,9 @@ mod tests {
 
         //3. Verify
         let t2 = std::time::Instant::now();
-        let verified = Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(
-            &params.1, &inputs, &proof,
-        )?;
+        let pvk = Groth16::<_, CircomCircuit<Bn256>>::process_vk(&params.1);
+        let verified =
+            Groth16::<_, CircomCircuit<Bn256>>::verify_with_prepared_vk(&pvk, &inputs, &proof)?;
         let elapsed2 = t2.elapsed().as_secs_f64();
         println!("3-groth16-bn128 verify run time: {} secs", elapsed2);
 
@@ -216,8 +662,9 @@ mod tests {
         //3. Verify
         let t2 = std::time::Instant::now();
         let inputs = circuit1.get_public_inputs().unwrap();
+        let pvk = Groth16::<_, CircomCircuit<Bls12>>::process_vk(&vk);
         let verified =
-            Groth16::<_, CircomCircuit<Bls12>>::verify_with_processed_vk(&vk, &inputs, &proof)?;
+            Groth16::<_, CircomCircuit<Bls12>>::verify_with_prepared_vk(&pvk, &inputs, &proof)?;
         let elapsed2 = t2.elapsed().as_secs_f64();
         println!("3-groth16-bls12381 verify run time: {} secs", elapsed2);
 
@@ -264,6 +711,550 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn groth16_verify_ct() -> Result<()> {
+        let (vk, inputs, proof) = multiplier_proof()?;
+        let mut bad_inputs = inputs.clone();
+        bad_inputs[0].add_assign(&Fr::one());
+        let mut bad_proof = proof.clone();
+        bad_proof.c = bad_proof.a;
+        for (inputs, proof, valid) in [
+            (&inputs, &proof, true),
+            (&bad_inputs, &proof, false),
+            (&inputs, &bad_proof, false),
+        ] {
+            let verified =
+                Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(&vk, inputs, proof)?;
+            let verified_ct = Groth16::<_, CircomCircuit<Bn256>>::verify_ct(&vk, inputs, proof)?;
+            assert_eq!(verified, valid);
+            assert_eq!(verified_ct, verified);
+        }
+        assert!(Groth16::<_, CircomCircuit<Bn256>>::verify_ct(&vk, &inputs[..0], &proof).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_load_prepared_vk_json() -> Result<()> {
+        let (vk, inputs, proof) = multiplier_proof()?;
+        let vk_file =
+            std::env::temp_dir().join(format!("eigen_groth16_vk_{}.json", std::process::id()));
+        let vk_file = vk_file.to_str().unwrap();
+        std::fs::write(vk_file, serialize_vk(&vk, "bn254", false)?)?;
+
+        let pvk = load_prepared_vk_json::<Bn256>(vk_file)?;
+        assert!(verify_proof(&pvk, &proof, &inputs)?);
+        let mut tampered = inputs.clone();
+        tampered[0].add_assign(&Fr::one());
+        assert!(!verify_proof(&pvk, &proof, &tampered)?);
+        assert!(load_prepared_vk_json::<Bls12>(vk_file).is_err());
+        match load_prepared_vk_json_for_curve("BN128", vk_file)? {
+            PreparedVk::BN128(pvk) => assert!(verify_proof(&pvk, &proof, &inputs)?),
+            _ => panic!("expected a BN128 key"),
+        }
+        std::fs::remove_file(vk_file)?;
+        assert!(load_prepared_vk_json::<Bn256>(vk_file).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_public_input_length() -> Result<()> {
+        let (vk, inputs, proof) = multiplier_proof()?;
+        let err = Groth16::<Bn256, CircomCircuit<Bn256>>::verify_with_processed_vk(
+            &vk,
+            &inputs[..inputs.len() - 1],
+            &proof,
+        )
+        .unwrap_err();
+        match err.downcast_ref::<EigenError>() {
+            Some(EigenError::PublicInputLength { expected, got }) => {
+                assert_eq!((*expected, *got), (inputs.len(), inputs.len() - 1))
+            }
+            _ => panic!("unexpected error: {}", err),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_pvk_round_trip() -> Result<()> {
+        let (vk, inputs, proof) = multiplier_proof()?;
+        let json = serialize_pvk(&vk)?;
+        let pvk = deserialize_pvk::<Bn256>(&json)?;
+        assert!(
+            Groth16::<Bn256, CircomCircuit<Bn256>>::verify_with_prepared_vk(&pvk, &inputs, &proof)?
+        );
+        let mut tampered = inputs.clone();
+        tampered[0].add_assign(&Fr::one());
+        assert!(
+            !Groth16::<Bn256, CircomCircuit<Bn256>>::verify_with_prepared_vk(
+                &pvk, &tampered, &proof
+            )?
+        );
+        assert!(deserialize_pvk::<Bls12>(&json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_zero_public_inputs() -> Result<()> {
+        // a * b = c with every signal private, so IC only holds the constant term
+        let one = Fr::one();
+        let r1cs = algebraic::circom_circuit::R1CS::<Bn256> {
+            num_inputs: 1,
+            num_aux: 3,
+            num_variables: 4,
+            num_outputs: 0,
+            num_prv_inputs: 2,
+            constraints: vec![(vec![(1, one)], vec![(2, one)], vec![(3, one)])],
+            custom_gates: vec![],
+            custom_gates_uses: vec![],
+        };
+        let circuit = CircomCircuit {
+            r1cs,
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        let mut rng = rand::thread_rng();
+        let (pk, vk) = Groth16::circuit_specific_setup(circuit.clone(), &mut rng)?;
+        assert_eq!(vk.ic.len(), 1);
+
+        let witness = ["1", "3", "11", "33"]
+            .iter()
+            .map(|v| Fr::from_str(v).unwrap())
+            .collect::<Vec<_>>();
+        let circuit = CircomCircuit {
+            witness: Some(witness),
+            ..circuit
+        };
+        assert_eq!(circuit.get_public_inputs_json(), "[]");
+        let inputs = circuit.get_public_inputs().unwrap();
+        let proof = Groth16::prove(&pk, circuit, &mut rng)?;
+
+        let inputs_json = serialize_input(&inputs, false)?;
+        assert_eq!(inputs_json, "[]");
+        let inputs = to_public_input::<Fr>(&inputs_json)?;
+        assert!(inputs.is_empty());
+        assert!(
+            Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(&vk, &inputs, &proof)?
+        );
+        assert!(Groth16::<_, CircomCircuit<Bn256>>::verify_ct(
+            &vk, &inputs, &proof
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_setup_archive() -> Result<()> {
+        let (circuit, pk, _) = match groth16_setup_inplace("BN128", CIRCUIT_FILE)? {
+            SetupResult::BN128(circuit, pk, vk) => (circuit, pk, vk),
+            _ => panic!("Expected BN128 setup result"),
+        };
+        let archive =
+            std::env::temp_dir().join(format!("eigen_groth16_setup_{}.bin", std::process::id()));
+        let archive = archive.to_str().unwrap();
+        save_setup_archive(archive, &pk, "BN128")?;
+        let loaded = load_setup_archive::<Bn256>(archive, "bn254")?;
+        assert!(load_setup_archive::<Bls12>(archive, "BLS12381").is_err());
+        std::fs::remove_file(archive)?;
+
+        let mut wtns = WitnessCalculator::from_file(WASM_FILE)?;
+        let inputs = load_input_for_witness(INPUT_FILE);
+        let w = wtns.calculate_witness(inputs, false)?;
+        let circuit = create_circuit_add_witness::<Bn256>(circuit, w);
+        let inputs = circuit.get_public_inputs().unwrap();
+        let mut rng = rand::thread_rng();
+        let proof = Groth16::prove(&loaded, circuit, &mut rng)?;
+        assert!(
+            Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(
+                &loaded.vk, &inputs, &proof
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_setup_deterministic() -> Result<()> {
+        let circuit: CircomCircuit<Bn256> = CircomCircuit {
+            r1cs: reader::load_r1cs(CIRCUIT_FILE),
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        let vk_bytes = |seed: [u8; 32]| -> Result<Vec<u8>> {
+            let (_, vk) = Groth16::setup_deterministic(circuit.clone(), seed)?;
+            let mut bytes = vec![];
+            vk.write(&mut bytes)?;
+            Ok(bytes)
+        };
+        assert_eq!(vk_bytes([7u8; 32])?, vk_bytes([7u8; 32])?);
+        assert_ne!(vk_bytes([7u8; 32])?, vk_bytes([8u8; 32])?);
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_prove_deterministic() -> Result<()> {
+        let circuit: CircomCircuit<Bn256> = CircomCircuit {
+            r1cs: reader::load_r1cs(CIRCUIT_FILE),
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        let (pk, vk) = Groth16::setup_deterministic(circuit.clone(), [1u8; 32])?;
+        let mut wtns = WitnessCalculator::from_file(WASM_FILE)?;
+        let w = wtns.calculate_witness(load_input_for_witness(INPUT_FILE), false)?;
+        let circuit = create_circuit_add_witness::<Bn256>(circuit, w);
+        let inputs = circuit.get_public_inputs().unwrap();
+
+        let proof_bytes = |seed: [u8; 32]| -> Result<Vec<u8>> {
+            let proof = Groth16::prove_deterministic(&pk, circuit.clone(), seed)?;
+            assert!(
+                Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(&vk, &inputs, &proof)?
+            );
+            let mut bytes = vec![];
+            proof.write(&mut bytes)?;
+            Ok(bytes)
+        };
+        assert_eq!(proof_bytes([7u8; 32])?, proof_bytes([7u8; 32])?);
+        assert_ne!(proof_bytes([7u8; 32])?, proof_bytes([8u8; 32])?);
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_proving_key_round_trip() -> Result<()> {
+        let circuit: CircomCircuit<Bn256> = CircomCircuit {
+            r1cs: reader::load_r1cs(CIRCUIT_FILE),
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        let mut rng = rand::thread_rng();
+        let (pk, vk) = Groth16::circuit_specific_setup(circuit.clone(), &mut rng)?;
+        let pk_file = std::env::temp_dir().join(format!("eigen_pk_{}.bin", std::process::id()));
+        let pk_file = pk_file.to_str().unwrap();
+        write_proving_key_to_file(&pk, pk_file)?;
+        let loaded = read_proving_key_from_file::<Bn256>(pk_file);
+        let bytes = std::fs::read(pk_file)?;
+        std::fs::remove_file(pk_file)?;
+        let loaded = loaded?;
+        assert!(loaded == pk);
+
+        let mut wtns = WitnessCalculator::from_file(WASM_FILE)?;
+        let w = wtns.calculate_witness(load_input_for_witness(INPUT_FILE), false)?;
+        let circuit = create_circuit_add_witness::<Bn256>(circuit, w);
+        let inputs = circuit.get_public_inputs().unwrap();
+        let proof = Groth16::prove(&loaded, circuit, &mut rng)?;
+        assert!(
+            Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(&vk, &inputs, &proof)?
+        );
+
+        assert!(read_proving_key::<Bls12, _>(&bytes[..]).is_err());
+        let mut bad_version = bytes.clone();
+        bad_version[4] = 2;
+        assert!(read_proving_key::<Bn256, _>(&bad_version[..]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_proof_from_wtns_file() -> Result<()> {
+        let r1cs = reader::load_r1cs::<Bn256>(CIRCUIT_FILE);
+        let circuit = CircomCircuit {
+            r1cs: r1cs.clone(),
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        let mut rng = rand::thread_rng();
+        let (pk, vk) = Groth16::circuit_specific_setup(circuit, &mut rng)?;
+
+        let mut wtns = WitnessCalculator::from_file(WASM_FILE)?;
+        let w = wtns.calculate_witness_bin(load_input_for_witness(INPUT_FILE), true)?;
+        let wtns_file =
+            std::env::temp_dir().join(format!("eigen_witness_{}.wtns", std::process::id()));
+        let wtns_file = wtns_file.to_str().unwrap();
+        wtns.save_witness_to_bin_file::<Bn256>(wtns_file, &w)?;
+        let circuit = CircomCircuit::with_witness_file(r1cs, wtns_file);
+        let bls12 = CircomCircuit::with_witness_file(
+            reader::load_r1cs::<Bls12>(CIRCUIT_FILE_BLS12),
+            wtns_file,
+        );
+        std::fs::remove_file(wtns_file)?;
+        assert!(bls12.is_err());
+
+        let circuit = circuit?;
+        let inputs = circuit.get_public_inputs()?;
+        let proof = Groth16::prove(&pk, circuit, &mut rng)?;
+        assert!(
+            Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(&vk, &inputs, &proof)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_rejects_custom_gates() {
+        let mut r1cs = reader::load_r1cs::<Bn256>(CIRCUIT_FILE);
+        r1cs.custom_gates
+            .push(algebraic::circom_circuit::CustomGates {
+                template_name: "Poseidon".to_string(),
+                parameters: vec![],
+            });
+        let circuit = CircomCircuit {
+            r1cs,
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        let err = Groth16::circuit_specific_setup(circuit, &mut rand::thread_rng()).unwrap_err();
+        assert!(err.to_string().contains("Poseidon"), "{}", err);
+    }
+
+    #[test]
+    fn groth16_estimate_prove_time() {
+        type G = Groth16<Bn256, CircomCircuit<Bn256>>;
+        let calibration = G::calibrate();
+        assert!(calibration.per_constraint > std::time::Duration::ZERO);
+        let small = G::estimate_prove_time(1_000, &calibration);
+        let large = G::estimate_prove_time(10_000, &calibration);
+        assert!(small > std::time::Duration::ZERO);
+        let ratio = large.as_secs_f64() / small.as_secs_f64();
+        assert!((ratio - 10.0).abs() < 0.01);
+        assert_eq!(
+            G::estimate_prove_time(0, &calibration),
+            std::time::Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn groth16_verify_from_hex() -> Result<()> {
+        let (vk, inputs, proof) = multiplier_proof()?;
+        let vk_json = serialize_vk(&vk, "bn128", false)?;
+        let proof_json = serialize_proof(&proof, "bn128", false)?;
+        let public_hex = inputs
+            .iter()
+            .map(|x| render_scalar_to_str(x, true))
+            .collect::<Vec<_>>();
+        assert!(public_hex.iter().all(|h| h.len() == 66));
+
+        let verified =
+            Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(&vk, &inputs, &proof)?;
+        assert!(verified);
+        assert_eq!(
+            verify_from_hex("bn128", &vk_json, &proof_json, &public_hex)?,
+            verified
+        );
+
+        let modulus =
+            "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001".to_string();
+        assert!(verify_from_hex("bn128", &vk_json, &proof_json, &[modulus]).is_err());
+        assert!(verify_from_hex("bn128", &vk_json, &proof_json, &["0x21".to_string()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_export_solidity_verifier() -> Result<()> {
+        use crate::api::export_solidity_verifier;
+        let (vk, inputs, _) = multiplier_proof()?;
+        let sol = export_solidity_verifier(&vk)?;
+        assert_eq!(sol.matches("vk.gamma_abc[").count(), inputs.len() + 1);
+        assert!(sol.contains(&format!("new Pairing.G1Point[]({})", inputs.len() + 1)));
+        assert!(sol.contains(&format!("uint[{}] memory input", inputs.len())));
+
+        let g1 = <Bls12 as Engine>::G1Affine::one();
+        let g2 = <Bls12 as Engine>::G2Affine::one();
+        let bls_vk = VerifyingKey::<Bls12> {
+            alpha_g1: g1,
+            beta_g1: g1,
+            beta_g2: g2,
+            gamma_g2: g2,
+            delta_g1: g1,
+            delta_g2: g2,
+            ic: vec![g1, g1],
+        };
+        assert!(export_solidity_verifier(&bls_vk).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_batch_verify() -> Result<()> {
+        let circuit: CircomCircuit<Bn256> = CircomCircuit {
+            r1cs: reader::load_r1cs(CIRCUIT_FILE),
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        let mut rng = rand::thread_rng();
+        let (pk, vk) = Groth16::circuit_specific_setup(circuit.clone(), &mut rng)?;
+
+        let mut wtns = WitnessCalculator::from_file(WASM_FILE)?;
+        let mut inputs = vec![];
+        let mut proofs = vec![];
+        for (a, b) in [(3u32, 11u32), (5, 7), (2, 2)] {
+            let w = wtns.calculate_witness(
+                vec![
+                    ("a".to_string(), vec![BigInt::from(a)]),
+                    ("b".to_string(), vec![BigInt::from(b)]),
+                ],
+                false,
+            )?;
+            let circuit = create_circuit_add_witness::<Bn256>(circuit.clone(), w);
+            inputs.push(circuit.get_public_inputs().unwrap());
+            proofs.push(Groth16::prove(&pk, circuit, &mut rng)?);
+        }
+        assert!(
+            Groth16::<_, CircomCircuit<Bn256>>::batch_verify_with_processed_vk(
+                &vk, &inputs, &proofs, &mut rng
+            )?
+        );
+
+        let mut tampered = inputs.clone();
+        tampered[1][0].add_assign(&Fr::one());
+        assert!(
+            !Groth16::<_, CircomCircuit<Bn256>>::batch_verify_with_processed_vk(
+                &vk, &tampered, &proofs, &mut rng
+            )?
+        );
+        let mut swapped = proofs.clone();
+        swapped.swap(0, 2);
+        assert!(
+            !Groth16::<_, CircomCircuit<Bn256>>::batch_verify_with_processed_vk(
+                &vk, &inputs, &swapped, &mut rng
+            )?
+        );
+        assert!(
+            Groth16::<_, CircomCircuit<Bn256>>::batch_verify_with_processed_vk(
+                &vk,
+                &[],
+                &[],
+                &mut rng
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_proof_bundle() -> Result<()> {
+        let (vk, inputs, proof) = multiplier_proof()?;
+        let bundle = serialize_proof_bundle(&proof, &inputs, "bn128")?;
+        let json: serde_json::Value = serde_json::from_str(&bundle)?;
+        assert_eq!(json["proof"]["protocol"], "groth16");
+        assert_eq!(
+            json["publicSignals"].as_array().map(|v| v.len()),
+            Some(inputs.len())
+        );
+
+        let (parsed_proof, parsed_inputs) = parse_proof_bundle::<Bn256>(&bundle)?;
+        assert_eq!(parsed_proof, proof);
+        assert_eq!(parsed_inputs, inputs);
+        assert_eq!(extract_and_validate_public(&bundle, &vk)?, inputs);
+
+        let mut bad_signal = json.clone();
+        bad_signal["publicSignals"][0] = "0xnot a scalar".into();
+        let err = parse_proof_bundle::<Bn256>(&bad_signal.to_string()).unwrap_err();
+        assert!(err.to_string().contains("signal 0"));
+
+        let mut extra = inputs.clone();
+        extra.push(Fr::one());
+        let bundle = serialize_proof_bundle(&proof, &extra, "bn128")?;
+        match extract_and_validate_public(&bundle, &vk) {
+            Err(EigenError::PublicInputLength { expected, got }) => {
+                assert_eq!((expected, got), (inputs.len(), extra.len()))
+            }
+            other => panic!("expect PublicInputLength, got {:?}", other),
+        }
+        assert!(
+            Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(
+                &vk,
+                &parsed_inputs,
+                &parsed_proof
+            )?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn groth16_prove_from_inputs() -> Result<()> {
+        let r1cs = reader::load_r1cs::<Bn256>(CIRCUIT_FILE);
+        let circuit = CircomCircuit {
+            r1cs: r1cs.clone(),
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        let mut rng = rand::thread_rng();
+        let (pk, vk) = Groth16::circuit_specific_setup(circuit, &mut rng)?;
+
+        let mut wtns = WitnessCalculator::from_file(WASM_FILE)?;
+        let inputs = load_input_for_witness(INPUT_FILE);
+        let proof = Groth16::prove_from_inputs(&pk, &mut wtns, &r1cs, inputs.clone(), &mut rng)?;
+        let public_inputs = wtns.calculate_witness_fr_parallel::<Bn256, _>(inputs.clone(), true)?
+            [1..r1cs.num_inputs]
+            .to_vec();
+        assert!(
+            Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(
+                &vk,
+                &public_inputs,
+                &proof
+            )?
+        );
+
+        let mut broken = r1cs.clone();
+        broken.constraints[0].2[0].1.add_assign(&Fr::one());
+        assert!(Groth16::prove_from_inputs(&pk, &mut wtns, &broken, inputs, &mut rng).is_err());
+        Ok(())
+    }
+    #[test]
+    fn groth16_debug_ic_sum() {
+        use crate::bellman_ce::bn256::{Fq, G1Affine};
+        let mut reader = std::io::BufReader::new(
+            std::fs::File::open("./test-vectors/verification_key.bin").unwrap(),
+        );
+        let vk = VerifyingKey::<Bn256>::read(&mut reader).unwrap();
+        let sum = Groth16::<Bn256, CircomCircuit<Bn256>>::debug_ic_sum(&vk);
+        let expected = G1Affine::from_xy_checked(
+            Fq::from_str(
+                "4986446495274958833087018415593674258060858526381454082593188548527281325843",
+            )
+            .unwrap(),
+            Fq::from_str(
+                "15027776662510914818388750492740636632752754427987765890844830461386598494056",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(sum.into_affine(), expected);
+    }
+    #[test]
+    fn groth16_prove_with_progress() -> Result<()> {
+        let circuit: CircomCircuit<Bn256> = CircomCircuit {
+            r1cs: reader::load_r1cs(CIRCUIT_FILE),
+            witness: None,
+            wire_mapping: None,
+            aux_offset: 0,
+        };
+        let mut rng = rand::thread_rng();
+        let (pk, vk) = Groth16::circuit_specific_setup(circuit.clone(), &mut rng)?;
+
+        let mut wtns = WitnessCalculator::from_file(WASM_FILE)?;
+        let w = wtns.calculate_witness(load_input_for_witness(INPUT_FILE), false)?;
+        let circuit = create_circuit_add_witness::<Bn256>(circuit, w);
+        let inputs = circuit.get_public_inputs().unwrap();
+        let phases = std::cell::RefCell::new(vec![]);
+        let proof = Groth16::prove_with_progress(&pk, circuit, &mut rng, |phase| {
+            phases.borrow_mut().push(phase)
+        })?;
+        assert_eq!(
+            phases.into_inner(),
+            vec![
+                ProvePhase::WitnessAssignment,
+                ProvePhase::FftAndMultiexp,
+                ProvePhase::Done
+            ]
+        );
+        assert!(
+            Groth16::<_, CircomCircuit<Bn256>>::verify_with_processed_vk(&vk, &inputs, &proof)?
+        );
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -315,8 +1306,9 @@ mod tests {
         //3. Verify
         let t2 = std::time::Instant::now();
         let inputs = circuit1.get_public_inputs().unwrap();
+        let pvk = Groth16::<_, CircomCircuit<Scalar>>::process_vk(&vk);
         let verified =
-            Groth16::<_, CircomCircuit<Scalar>>::verify_with_processed_vk(&vk, &inputs, &proof)?;
+            Groth16::<_, CircomCircuit<Scalar>>::verify_with_prepared_vk(&pvk, &inputs, &proof)?;
         let elapsed2 = t2.elapsed().as_secs_f64();
         info!("3-groth16-bls12381 verify run time: {} secs", elapsed2);
 
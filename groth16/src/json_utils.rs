@@ -1,44 +1,184 @@
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 use crate::bellman_ce::pairing::{bls12_381::Bls12, bn256::Bn256};
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
+use algebraic::errors::EigenError;
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
 use algebraic::utils::repr_to_big;
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 use algebraic::PrimeField;
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use algebraic_gpu::circom_circuit::repr_to_big;
-use anyhow::Result;
+use anyhow::{bail, Result};
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use bellperson::groth16::*;
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use blstrs::{Bls12, Fp, Fp2, G1Affine, G2Affine, Scalar};
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 use franklin_crypto::bellman::{
-    bls12_381::{
-        Fq2 as Fq2_bls12381, G1Affine as G1Affine_bls12381, G2Affine as G2Affine_bls12381,
-    },
-    bn256::{Fq2, G1Affine, G2Affine},
+    bls12_381::{Fq as Fq_bls12381, Fq12 as Fq12_bls12381, Fq2 as Fq2_bls12381},
+    bn256::{Fq, Fq12, Fq2},
+    compact_bn256::Bn256 as CompactBn256,
     groth16::{Proof, VerifyingKey},
-    CurveAffine,
+    CurveAffine, Field,
 };
 use num_bigint::BigUint;
 use num_traits::Num;
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 use pairing::MultiMillerLoop;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::to_string;
 use std::fmt;
-#[derive(Debug, Serialize, Deserialize)]
+
+/// Written as `{"x": .., "y": ..}`, or snarkjs's `[x, y, "1"]` with the `snarkjs_compat`
+/// feature, the identity being `(0, 0)` and `["0", "1", "0"]`. Both forms are read, and a
+/// bare `[x, y]`, the projective coordinate checked to be 1, or 0 for the identity.
+#[derive(Debug)]
 pub struct G1 {
     pub x: String,
     pub y: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Like `G1`, snarkjs's form being `[[x0, x1], [y0, y1], ["1", "0"]]` and the identity
+/// `[["0", "0"], ["1", "0"], ["0", "0"]]`.
+#[derive(Debug)]
 pub struct G2 {
     pub x: [String; 2],
     pub y: [String; 2],
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum G1Repr {
+    Affine { x: String, y: String },
+    Projective(Vec<String>),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum G2Repr {
+    Affine { x: [String; 2], y: [String; 2] },
+    Projective(Vec<[String; 2]>),
+}
+
+// `parse_g1_json` and `parse_g2_json` write the identity as all zero coordinates, in decimal
+// or hex, which no point of either curve has
+fn is_zero_coordinate(c: &str) -> bool {
+    c.strip_prefix("0x").unwrap_or(c).bytes().all(|b| b == b'0')
+}
+
+impl Serialize for G1 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if !cfg!(feature = "snarkjs_compat") {
+            return G1Repr::Affine {
+                x: self.x.clone(),
+                y: self.y.clone(),
+            }
+            .serialize(serializer);
+        }
+        let coordinates = if is_zero_coordinate(&self.x) && is_zero_coordinate(&self.y) {
+            ["0", "1", "0"].map(String::from).to_vec()
+        } else {
+            vec![self.x.clone(), self.y.clone(), "1".to_string()]
+        };
+        G1Repr::Projective(coordinates).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for G1 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let c = match G1Repr::deserialize(deserializer)? {
+            G1Repr::Affine { x, y } => return Ok(G1 { x, y }),
+            G1Repr::Projective(c) => c,
+        };
+        match c.len() {
+            2 => {}
+            3 if c[2] == "1" => {}
+            3 if c[2] == "0" => {
+                return Ok(G1 {
+                    x: "0".to_string(),
+                    y: "0".to_string(),
+                })
+            }
+            3 => {
+                return Err(serde::de::Error::custom(format!(
+                    "expect the G1 z coordinate to be 1 or 0, got {}",
+                    c[2]
+                )))
+            }
+            n => {
+                return Err(serde::de::Error::custom(format!(
+                    "expect 2 or 3 G1 coordinates, got {}",
+                    n
+                )))
+            }
+        }
+        Ok(G1 {
+            x: c[0].clone(),
+            y: c[1].clone(),
+        })
+    }
+}
+
+impl Serialize for G2 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        if !cfg!(feature = "snarkjs_compat") {
+            return G2Repr::Affine {
+                x: self.x.clone(),
+                y: self.y.clone(),
+            }
+            .serialize(serializer);
+        }
+        let pair = |a: &str, b: &str| [a.to_string(), b.to_string()];
+        let coordinates = if self
+            .x
+            .iter()
+            .chain(self.y.iter())
+            .all(|c| is_zero_coordinate(c))
+        {
+            vec![pair("0", "0"), pair("1", "0"), pair("0", "0")]
+        } else {
+            vec![self.x.clone(), self.y.clone(), pair("1", "0")]
+        };
+        G2Repr::Projective(coordinates).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for G2 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let c = match G2Repr::deserialize(deserializer)? {
+            G2Repr::Affine { x, y } => return Ok(G2 { x, y }),
+            G2Repr::Projective(c) => c,
+        };
+        match c.len() {
+            2 => {}
+            3 if c[2] == ["1", "0"] => {}
+            3 if c[2] == ["0", "0"] => {
+                let zero = || ["0".to_string(), "0".to_string()];
+                return Ok(G2 {
+                    x: zero(),
+                    y: zero(),
+                });
+            }
+            3 => {
+                return Err(serde::de::Error::custom(format!(
+                    "expect the G2 z coordinate to be [1, 0] or [0, 0], got {:?}",
+                    c[2]
+                )))
+            }
+            n => {
+                return Err(serde::de::Error::custom(format!(
+                    "expect 2 or 3 G2 coordinates, got {}",
+                    n
+                )))
+            }
+        }
+        Ok(G2 {
+            x: c[0].clone(),
+            y: c[1].clone(),
+        })
+    }
+}
+
 impl fmt::Display for G1 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{}, {}", self.x, self.y)
@@ -91,11 +231,56 @@ pub struct ProofFile {
     pub curve: String,
 }
 
+/// snarkjs's combined `{ "proof": ..., "publicSignals": [...] }` object
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProofBundleFile {
+    pub proof: ProofFile,
+    #[serde(rename = "publicSignals")]
+    pub public_signals: Vec<String>,
+}
+
+/// snarkjs's `proof.json`: projective points in decimal, G1 as `[x, y, z]` and G2 as
+/// `[[x0, x1], [y0, y1], [z0, z1]]`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnarkjsProofFile {
+    pub pi_a: [String; 3],
+    pub pi_b: [[String; 2]; 3],
+    pub pi_c: [String; 3],
+    pub protocol: String,
+    pub curve: String,
+}
+
+/// snarkjs's `verification_key.json`, which has no `vk_beta_1` nor `vk_delta_1` but the
+/// precomputed `e(alpha, beta)`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnarkjsVerifyingKeyFile {
+    pub protocol: String,
+    pub curve: String,
+    #[serde(rename = "nPublic")]
+    pub n_public: usize,
+    pub vk_alpha_1: [String; 3],
+    pub vk_beta_2: [[String; 2]; 3],
+    pub vk_gamma_2: [[String; 2]; 3],
+    pub vk_delta_2: [[String; 2]; 3],
+    pub vk_alphabeta_12: [[[String; 2]; 3]; 2],
+    #[serde(rename = "IC")]
+    pub ic: Vec<[String; 3]>,
+}
+
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
 pub trait Parser: franklin_crypto::bellman::pairing::Engine {
     fn parse_g1(e: &Self::G1Affine, to_hex: bool) -> (String, String);
     fn parse_g2(e: &Self::G2Affine, to_hex: bool) -> (String, String, String, String);
+    /// The identity, whose affine coordinates `parse_g1` gives as `(0, 1)`, is written as
+    /// `(0, 0)`.
     fn parse_g1_json(e: &Self::G1Affine, to_hex: bool) -> G1 {
+        if e.is_zero() {
+            let zero = render_scalar_to_str(&Self::Fq::zero(), to_hex);
+            return G1 {
+                x: zero.clone(),
+                y: zero,
+            };
+        }
         let parsed = Self::parse_g1(e, to_hex);
         G1 {
             x: parsed.0,
@@ -103,14 +288,27 @@ pub trait Parser: franklin_crypto::bellman::pairing::Engine {
         }
     }
     fn parse_g2_json(e: &Self::G2Affine, to_hex: bool) -> G2 {
+        if e.is_zero() {
+            let zero = render_scalar_to_str(&Self::Fq::zero(), to_hex);
+            return G2 {
+                x: [zero.clone(), zero.clone()],
+                y: [zero.clone(), zero],
+            };
+        }
         let parsed = Self::parse_g2(e, to_hex);
         G2 {
             x: (parsed.0, parsed.1).into(),
             y: (parsed.2, parsed.3).into(),
         }
     }
-    fn to_g1(x: &str, y: &str) -> Self::G1Affine;
-    fn to_g2(x0: &str, x1: &str, y0: &str, y1: &str) -> Self::G2Affine;
+    /// Build a point from its coordinates, each checked by the same rules as a public input,
+    /// the point itself not checked to be on the curve. All zero coordinates are the identity.
+    fn to_g1(x: &str, y: &str) -> Result<Self::G1Affine>;
+    fn to_g2(x0: &str, x1: &str, y0: &str, y1: &str) -> Result<Self::G2Affine>;
+    /// The normalized `curve` field of this engine's files.
+    fn curve_name() -> &'static str;
+    /// The 12 base field coefficients of a pairing result, tower order, in decimal.
+    fn parse_fqk(e: &Self::Fqk) -> Vec<String>;
 }
 
 #[cfg(any(feature = "cuda", feature = "opencl"))]
@@ -145,27 +343,119 @@ pub fn render_scalar_to_str<F: PrimeField>(el: &F, to_hex: bool) -> String {
     }
 }
 
+/// Parse a decimal or `0x` prefixed hex scalar, panicking on anything else, so only for
+/// values already checked or trusted, `render_hex_to_scalar` is the fallible one.
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
+#[deprecated(note = "use render_hex_to_scalar")]
 pub fn render_str_to_scalar<F: PrimeField>(value: &str) -> F {
-    let value = match value.starts_with("0x") {
-        true => BigUint::from_str_radix(&value[2..], 16)
-            .unwrap()
-            .to_str_radix(10),
-        _ => value.to_string(),
+    match value.starts_with("0x") {
+        true => render_hex_to_scalar(value, false, Endianness::Big).unwrap(),
+        _ => F::from_str(value).unwrap(),
+    }
+}
+
+/// Byte order of a hex scalar. `render_scalar_to_str` and the serializers write big-endian
+/// unless asked for little-endian.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Endianness {
+    Big,
+    Little,
+}
+
+/// Parse a hex scalar from an external verifying key or public input, `0x` prefixed unless
+/// `allow_bare`, rejecting non hex digits and values not below the modulus. A big-endian
+/// value may have an odd number of digits, a little-endian one must be whole bytes.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn render_hex_to_scalar<F: PrimeField>(
+    value: &str,
+    allow_bare: bool,
+    endianness: Endianness,
+) -> std::result::Result<F, EigenError> {
+    let digits = match value.strip_prefix("0x") {
+        Some(digits) => digits,
+        None if allow_bare => value,
+        None => {
+            return Err(EigenError::InvalidScalar(format!(
+                "{:?} is missing the 0x prefix",
+                value
+            )))
+        }
     };
-    F::from_str(&value).unwrap()
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(EigenError::InvalidScalar(format!("{:?} is not hex", value)));
+    }
+    let digits = match endianness {
+        Endianness::Big => digits.to_string(),
+        Endianness::Little if digits.len() % 2 == 1 => {
+            return Err(EigenError::InvalidScalar(format!(
+                "{:?} is not whole bytes",
+                value
+            )))
+        }
+        Endianness::Little => reverse_hex_bytes(digits),
+    };
+    let v = BigUint::from_str_radix(&digits, 16)
+        .map_err(|e| EigenError::InvalidScalar(format!("{:?}, {}", value, e)))?;
+    let modulus = BigUint::from_str_radix(&repr_to_big(F::char()), 10)
+        .map_err(|e| EigenError::Unknown(e.to_string()))?;
+    if v >= modulus {
+        return Err(EigenError::InvalidScalar(format!(
+            "{:?} is not below the modulus",
+            value
+        )));
+    }
+    F::from_str(&v.to_str_radix(10))
+        .ok_or_else(|| EigenError::InvalidScalar(format!("{:?}", value)))
+}
+
+/// Render `el` as `0x` followed by its little-endian bytes, unlike `render_scalar_to_str`
+/// which writes hex big-endian.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn render_scalar_to_hex_le<F: PrimeField>(el: &F) -> String {
+    use algebraic::PrimeFieldRepr;
+    let mut bytes = vec![];
+    el.into_repr().write_le(&mut bytes).unwrap();
+    format!("0x{}", hex::encode(bytes))
 }
 
+/// Render public inputs as a snarkjs `public.json` array, an empty slice gives `[]`.
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
-pub fn to_public_input<T: PrimeField>(s: &str) -> Vec<T> {
-    let input: Vec<String> = serde_json::from_str(s).unwrap();
+pub fn serialize_input<T: PrimeField>(inputs: &[T], to_hex: bool) -> Result<String> {
+    let inputs = inputs
+        .iter()
+        .map(|x| render_scalar_to_str(x, to_hex))
+        .collect::<Vec<_>>();
+    Ok(to_string(&inputs)?)
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn to_public_input<T: PrimeField>(s: &str) -> std::result::Result<Vec<T>, EigenError> {
+    let input: Vec<String> =
+        serde_json::from_str(s).map_err(|e| EigenError::InvalidPublicInputs(e.to_string()))?;
     input
         .iter()
-        .map(|hex_str| render_str_to_scalar::<T>(hex_str))
+        .enumerate()
+        .map(|(i, hex_str)| {
+            parse_coordinate::<T>(hex_str)
+                .map_err(|e| EigenError::InvalidPublicInputs(format!("input {}: {}", i, e)))
+        })
         .collect()
 }
+/// What a curve needs to get a `Parser`: splitting the G2 coordinates, elements of the
+/// quadratic extension `Fqe`, into their two `Fq` halves and back. The JSON layout is the
+/// same for every curve.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub trait CurveCoordinates: franklin_crypto::bellman::pairing::Engine {
+    /// as returned by `normalize_curve_name`
+    const CURVE: &'static str;
+    fn fqe_to_fq(e: &Self::Fqe) -> (Self::Fq, Self::Fq);
+    fn fq_to_fqe(c0: Self::Fq, c1: Self::Fq) -> Self::Fqe;
+    /// `Fq12 = Fq6[w]`, `Fq6 = Fq2[v]`, flattened as `c0.c0.c0, c0.c0.c1, c0.c1.c0, ..`
+    fn fqk_to_fq(e: &Self::Fqk) -> Vec<Self::Fq>;
+}
+
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
-impl Parser for Bn256 {
+impl<E: CurveCoordinates> Parser for E {
     fn parse_g1(e: &Self::G1Affine, to_hex: bool) -> (String, String) {
         let (x, y) = e.into_xy_unchecked();
         (
@@ -176,65 +466,99 @@ impl Parser for Bn256 {
 
     fn parse_g2(e: &Self::G2Affine, to_hex: bool) -> (String, String, String, String) {
         let (x, y) = e.into_xy_unchecked();
+        let (x0, x1) = Self::fqe_to_fq(&x);
+        let (y0, y1) = Self::fqe_to_fq(&y);
         (
-            render_scalar_to_str(&x.c0, to_hex),
-            render_scalar_to_str(&x.c1, to_hex),
-            render_scalar_to_str(&y.c0, to_hex),
-            render_scalar_to_str(&y.c1, to_hex),
+            render_scalar_to_str(&x0, to_hex),
+            render_scalar_to_str(&x1, to_hex),
+            render_scalar_to_str(&y0, to_hex),
+            render_scalar_to_str(&y1, to_hex),
         )
     }
 
-    fn to_g1(x: &str, y: &str) -> Self::G1Affine {
-        G1Affine::from_xy_unchecked(render_str_to_scalar(x), render_str_to_scalar(y))
+    fn to_g1(x: &str, y: &str) -> Result<Self::G1Affine> {
+        let (x, y) = (parse_coordinate::<Self::Fq>(x)?, parse_coordinate(y)?);
+        if x.is_zero() && y.is_zero() {
+            return Ok(Self::G1Affine::zero());
+        }
+        Ok(Self::G1Affine::from_xy_unchecked(x, y))
     }
 
-    fn to_g2(x0: &str, x1: &str, y0: &str, y1: &str) -> Self::G2Affine {
-        let x = Fq2 {
-            c0: render_str_to_scalar(x0),
-            c1: render_str_to_scalar(x1),
-        };
-        let y = Fq2 {
-            c0: render_str_to_scalar(y0),
-            c1: render_str_to_scalar(y1),
-        };
-        G2Affine::from_xy_unchecked(x, y)
+    fn to_g2(x0: &str, x1: &str, y0: &str, y1: &str) -> Result<Self::G2Affine> {
+        let x = Self::fq_to_fqe(parse_coordinate(x0)?, parse_coordinate(x1)?);
+        let y = Self::fq_to_fqe(parse_coordinate(y0)?, parse_coordinate(y1)?);
+        if x.is_zero() && y.is_zero() {
+            return Ok(Self::G2Affine::zero());
+        }
+        Ok(Self::G2Affine::from_xy_unchecked(x, y))
+    }
+
+    fn curve_name() -> &'static str {
+        Self::CURVE
+    }
+
+    fn parse_fqk(e: &Self::Fqk) -> Vec<String> {
+        Self::fqk_to_fq(e)
+            .iter()
+            .map(|c| render_scalar_to_str(c, false))
+            .collect()
     }
 }
 
 #[cfg(not(any(feature = "cuda", feature = "opencl")))]
-impl Parser for Bls12 {
-    fn parse_g1(e: &Self::G1Affine, to_hex: bool) -> (String, String) {
-        let (x, y) = e.into_xy_unchecked();
-        (
-            render_scalar_to_str(&x, to_hex),
-            render_scalar_to_str(&y, to_hex),
-        )
+impl CurveCoordinates for Bn256 {
+    const CURVE: &'static str = "bn128";
+    fn fqe_to_fq(e: &Fq2) -> (Fq, Fq) {
+        (e.c0, e.c1)
     }
 
-    fn parse_g2(e: &Self::G2Affine, to_hex: bool) -> (String, String, String, String) {
-        let (x, y) = e.into_xy_unchecked();
-        (
-            render_scalar_to_str(&x.c0, to_hex),
-            render_scalar_to_str(&x.c1, to_hex),
-            render_scalar_to_str(&y.c0, to_hex),
-            render_scalar_to_str(&y.c1, to_hex),
-        )
+    fn fq_to_fqe(c0: Fq, c1: Fq) -> Fq2 {
+        Fq2 { c0, c1 }
     }
 
-    fn to_g1(x: &str, y: &str) -> Self::G1Affine {
-        G1Affine_bls12381::from_xy_unchecked(render_str_to_scalar(x), render_str_to_scalar(y))
+    fn fqk_to_fq(e: &Fq12) -> Vec<Fq> {
+        [e.c0, e.c1]
+            .iter()
+            .flat_map(|c| [c.c0, c.c1, c.c2])
+            .flat_map(|c| [c.c0, c.c1])
+            .collect()
     }
+}
 
-    fn to_g2(x0: &str, x1: &str, y0: &str, y1: &str) -> Self::G2Affine {
-        let x = Fq2_bls12381 {
-            c0: render_str_to_scalar(x0),
-            c1: render_str_to_scalar(x1),
-        };
-        let y = Fq2_bls12381 {
-            c0: render_str_to_scalar(y0),
-            c1: render_str_to_scalar(y1),
-        };
-        G2Affine_bls12381::from_xy_unchecked(x, y)
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+impl CurveCoordinates for Bls12 {
+    const CURVE: &'static str = "bls12381";
+    fn fqe_to_fq(e: &Fq2_bls12381) -> (Fq_bls12381, Fq_bls12381) {
+        (e.c0, e.c1)
+    }
+
+    fn fq_to_fqe(c0: Fq_bls12381, c1: Fq_bls12381) -> Fq2_bls12381 {
+        Fq2_bls12381 { c0, c1 }
+    }
+
+    fn fqk_to_fq(e: &Fq12_bls12381) -> Vec<Fq_bls12381> {
+        [e.c0, e.c1]
+            .iter()
+            .flat_map(|c| [c.c0, c.c1, c.c2])
+            .flat_map(|c| [c.c0, c.c1])
+            .collect()
+    }
+}
+
+/// BN254 with the compact point representation, same fields as `Bn256`.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+impl CurveCoordinates for CompactBn256 {
+    const CURVE: &'static str = "bn128";
+    fn fqe_to_fq(e: &Fq2) -> (Fq, Fq) {
+        (e.c0, e.c1)
+    }
+
+    fn fq_to_fqe(c0: Fq, c1: Fq) -> Fq2 {
+        Fq2 { c0, c1 }
+    }
+
+    fn fqk_to_fq(e: &Fq12) -> Vec<Fq> {
+        Bn256::fqk_to_fq(e)
     }
 }
 
@@ -274,30 +598,35 @@ pub fn render_str_to_fp(value: &str) -> Fp {
     Fp::from_bytes_be(&be_bytes).unwrap()
 }
 
+/// Parse a decimal or `0x` prefixed hex scalar, failing on anything not below the modulus.
 #[cfg(any(feature = "cuda", feature = "opencl"))]
-pub fn render_str_to_scalar(value: &str) -> Scalar {
+pub fn render_str_to_scalar(value: &str) -> Result<Scalar> {
     let mut be_bytes = [0u8; 32];
-    let hex_str;
-    if value.starts_with("0x") {
-        hex_str = value[2..].to_string();
-    } else {
-        let big_uint = BigUint::from_str_radix(value, 10).unwrap();
-        hex_str = big_uint.to_str_radix(16);
-    }
+    let hex_str = match value.strip_prefix("0x") {
+        Some(digits) => digits.to_string(),
+        None => BigUint::from_str_radix(value, 10)
+            .map_err(|e| anyhow::anyhow!("invalid scalar {:?}, {}", value, e))?
+            .to_str_radix(16),
+    };
     let final_hex_str = if hex_str.len() % 2 != 0 {
         format!("0{}", hex_str)
     } else {
         hex_str
     };
-    let bytes = hex::decode(final_hex_str).expect("Invalid hex string");
-    let start = 32 - bytes.len();
+    let bytes = hex::decode(final_hex_str)
+        .map_err(|e| anyhow::anyhow!("invalid scalar {:?}, {}", value, e))?;
+    if bytes.len() > be_bytes.len() {
+        bail!("invalid scalar {:?}, longer than 32 bytes", value);
+    }
+    let start = be_bytes.len() - bytes.len();
     be_bytes[start..].copy_from_slice(&bytes);
-    Scalar::from_bytes_be(&be_bytes).unwrap()
+    Option::from(Scalar::from_bytes_be(&be_bytes))
+        .ok_or_else(|| anyhow::anyhow!("invalid scalar {:?}, not below the modulus", value))
 }
 
 #[cfg(any(feature = "cuda", feature = "opencl"))]
-pub fn to_public_input(s: &str) -> Vec<Scalar> {
-    let input: Vec<String> = serde_json::from_str(s).unwrap();
+pub fn to_public_input(s: &str) -> Result<Vec<Scalar>> {
+    let input: Vec<String> = serde_json::from_str(s)?;
     input
         .iter()
         .map(|hex_str| render_str_to_scalar(hex_str))
@@ -332,6 +661,21 @@ impl Parser for Bls12 {
     }
 }
 
+/// Map the common spellings of a curve name onto the one snarkjs writes into
+/// `verification_key.json` and `proof.json`.
+pub fn normalize_curve_name(curve_type: &str) -> Result<&'static str> {
+    let name = curve_type
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .collect::<String>()
+        .to_lowercase();
+    match name.as_str() {
+        "bn128" | "bn254" | "bn256" | "altbn128" => Ok("bn128"),
+        "bls12381" => Ok("bls12381"),
+        _ => bail!("Unknown curve type: {}", curve_type),
+    }
+}
+
 pub fn serialize_vk<P: Parser>(
     vk: &VerifyingKey<P>,
     curve_type: &str,
@@ -339,7 +683,7 @@ pub fn serialize_vk<P: Parser>(
 ) -> Result<String> {
     let verifying_key_file = VerifyingKeyFile {
         protocol: "groth16".to_string(),
-        curve: curve_type.to_string(),
+        curve: normalize_curve_name(curve_type)?.to_string(),
         alpha_g1: P::parse_g1_json(&vk.alpha_g1, to_hex),
         beta_g1: P::parse_g1_json(&vk.beta_g1, to_hex),
         beta_g2: P::parse_g2_json(&vk.beta_g2, to_hex),
@@ -362,20 +706,427 @@ pub fn serialize_proof<P: Parser>(p: &Proof<P>, curve_type: &str, to_hex: bool)
         b: P::parse_g2_json(&p.b, to_hex),
         c: P::parse_g1_json(&p.c, to_hex),
         protocol: "groth16".to_string(),
-        curve: curve_type.to_string(),
+        curve: normalize_curve_name(curve_type)?.to_string(),
     };
 
     Ok(to_string(&proof_file)?)
 }
 
-pub fn to_verification_key<P: Parser>(s: &str) -> VerifyingKey<P> {
+// reverse the bytes of hex digits, an odd length is padded with a leading zero
+fn reverse_hex_bytes(digits: &str) -> String {
+    let digits = match digits.len() % 2 {
+        0 => digits.to_string(),
+        _ => format!("0{}", digits),
+    };
+    digits
+        .as_bytes()
+        .chunks(2)
+        .rev()
+        .map(|b| std::str::from_utf8(b).unwrap())
+        .collect()
+}
+
+// swap the byte order of a `0x` prefixed hex string, its own inverse
+fn swap_hex_endianness(value: &str) -> String {
+    format!(
+        "0x{}",
+        reverse_hex_bytes(value.strip_prefix("0x").unwrap_or(value))
+    )
+}
+
+fn g1_swap_endianness(p: G1) -> G1 {
+    G1 {
+        x: swap_hex_endianness(&p.x),
+        y: swap_hex_endianness(&p.y),
+    }
+}
+
+fn g2_swap_endianness(p: G2) -> G2 {
+    G2 {
+        x: [swap_hex_endianness(&p.x[0]), swap_hex_endianness(&p.x[1])],
+        y: [swap_hex_endianness(&p.y[0]), swap_hex_endianness(&p.y[1])],
+    }
+}
+
+fn vk_file_swap_endianness(vk_file: VerifyingKeyFile) -> VerifyingKeyFile {
+    VerifyingKeyFile {
+        alpha_g1: g1_swap_endianness(vk_file.alpha_g1),
+        beta_g1: g1_swap_endianness(vk_file.beta_g1),
+        beta_g2: g2_swap_endianness(vk_file.beta_g2),
+        gamma_g2: g2_swap_endianness(vk_file.gamma_g2),
+        delta_g1: g1_swap_endianness(vk_file.delta_g1),
+        delta_g2: g2_swap_endianness(vk_file.delta_g2),
+        ic: vk_file.ic.into_iter().map(g1_swap_endianness).collect(),
+        ..vk_file
+    }
+}
+
+fn proof_file_swap_endianness(proof_file: ProofFile) -> ProofFile {
+    ProofFile {
+        a: g1_swap_endianness(proof_file.a),
+        b: g2_swap_endianness(proof_file.b),
+        c: g1_swap_endianness(proof_file.c),
+        ..proof_file
+    }
+}
+
+/// Same as `serialize_vk` with `to_hex`, the coordinates are written big-endian by default
+/// and little-endian with `little_endian`.
+pub fn serialize_vk_hex<P: Parser>(
+    vk: &VerifyingKey<P>,
+    curve_type: &str,
+    little_endian: bool,
+) -> Result<String> {
+    let json = serialize_vk(vk, curve_type, true)?;
+    if !little_endian {
+        return Ok(json);
+    }
+    let vk_file: VerifyingKeyFile = serde_json::from_str(&json)?;
+    Ok(to_string(&vk_file_swap_endianness(vk_file))?)
+}
+
+/// Same as `serialize_proof` with `to_hex`, the coordinates are written big-endian by
+/// default and little-endian with `little_endian`.
+pub fn serialize_proof_hex<P: Parser>(
+    p: &Proof<P>,
+    curve_type: &str,
+    little_endian: bool,
+) -> Result<String> {
+    let json = serialize_proof(p, curve_type, true)?;
+    if !little_endian {
+        return Ok(json);
+    }
+    let proof_file: ProofFile = serde_json::from_str(&json)?;
+    Ok(to_string(&proof_file_swap_endianness(proof_file))?)
+}
+
+/// Parse a verifying key written by `serialize_vk_hex` with the same `little_endian`.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn to_verification_key_hex<P: Parser>(s: &str, little_endian: bool) -> Result<VerifyingKey<P>> {
+    if !little_endian {
+        return Ok(to_verification_key::<P>(s)?);
+    }
+    let vk_file: VerifyingKeyFile = serde_json::from_str(s)?;
+    let json = to_string(&vk_file_swap_endianness(vk_file))?;
+    Ok(to_verification_key::<P>(&json)?)
+}
+
+/// Parse a proof written by `serialize_proof_hex` with the same `little_endian`.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn to_proof_hex<P: Parser>(s: &str, little_endian: bool) -> Result<Proof<P>> {
+    if !little_endian {
+        return Ok(to_proof::<P>(s)?);
+    }
+    let proof_file: ProofFile = serde_json::from_str(s)?;
+    let json = to_string(&proof_file_swap_endianness(proof_file))?;
+    Ok(to_proof::<P>(&json)?)
+}
+
+/// Serialize `proof` and its public inputs together as one snarkjs proof bundle, in
+/// decimal.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn serialize_proof_bundle<P: Parser>(
+    proof: &Proof<P>,
+    public_inputs: &[P::Fr],
+    curve_type: &str,
+) -> Result<String> {
+    let bundle = ProofBundleFile {
+        proof: serde_json::from_str(&serialize_proof(proof, curve_type, false)?)?,
+        public_signals: public_inputs
+            .iter()
+            .map(|x| render_scalar_to_str(x, false))
+            .collect(),
+    };
+    Ok(to_string(&bundle)?)
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+fn snarkjs_g1<P: Parser>(p: &P::G1Affine) -> [String; 3] {
+    if p.is_zero() {
+        return ["0".to_string(), "1".to_string(), "0".to_string()];
+    }
+    let (x, y) = P::parse_g1(p, false);
+    [x, y, "1".to_string()]
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+fn snarkjs_g2<P: Parser>(p: &P::G2Affine) -> [[String; 2]; 3] {
+    let pair = |a: &str, b: &str| [a.to_string(), b.to_string()];
+    if p.is_zero() {
+        return [pair("0", "0"), pair("1", "0"), pair("0", "0")];
+    }
+    let (x0, x1, y0, y1) = P::parse_g2(p, false);
+    [[x0, x1], [y0, y1], pair("1", "0")]
+}
+
+/// Serialize `proof` exactly as snarkjs writes a `proof.json`, decimal projective
+/// coordinates and the curve named as snarkjs does.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn serialize_proof_decimal<P: Parser>(proof: &Proof<P>) -> Result<String> {
+    let proof_file = SnarkjsProofFile {
+        pi_a: snarkjs_g1::<P>(&proof.a),
+        pi_b: snarkjs_g2::<P>(&proof.b),
+        pi_c: snarkjs_g1::<P>(&proof.c),
+        protocol: "groth16".to_string(),
+        curve: P::curve_name().to_string(),
+    };
+    Ok(to_string(&proof_file)?)
+}
+
+/// Serialize `vk` as snarkjs writes a `verification_key.json`, see `serialize_proof_decimal`.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn serialize_vk_decimal<P: Parser>(vk: &VerifyingKey<P>) -> Result<String> {
+    let alphabeta = P::parse_fqk(&P::pairing(vk.alpha_g1, vk.beta_g2));
+    let fq6 = |c: &[String]| {
+        [
+            [c[0].clone(), c[1].clone()],
+            [c[2].clone(), c[3].clone()],
+            [c[4].clone(), c[5].clone()],
+        ]
+    };
+    let vk_file = SnarkjsVerifyingKeyFile {
+        protocol: "groth16".to_string(),
+        curve: P::curve_name().to_string(),
+        n_public: vk.ic.len().saturating_sub(1),
+        vk_alpha_1: snarkjs_g1::<P>(&vk.alpha_g1),
+        vk_beta_2: snarkjs_g2::<P>(&vk.beta_g2),
+        vk_gamma_2: snarkjs_g2::<P>(&vk.gamma_g2),
+        vk_delta_2: snarkjs_g2::<P>(&vk.delta_g2),
+        vk_alphabeta_12: [fq6(&alphabeta[..6]), fq6(&alphabeta[6..])],
+        ic: vk.ic.iter().map(|p| snarkjs_g1::<P>(p)).collect(),
+    };
+    Ok(to_string(&vk_file)?)
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn parse_proof_bundle<P: Parser>(s: &str) -> Result<(Proof<P>, Vec<P::Fr>)> {
+    let bundle: ProofBundleFile = serde_json::from_str(s)?;
+    let proof = to_proof::<P>(&to_string(&bundle.proof)?)?;
+    let public_inputs = bundle
+        .public_signals
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            parse_coordinate::<P::Fr>(x).map_err(|e| anyhow::anyhow!("signal {}: {}", i, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((proof, public_inputs))
+}
+
+/// Check every IC point of `vk` is on the curve, in the prime order subgroup, not the
+/// identity and distinct from the others. A key from an untrusted source failing this may
+/// accept forged proofs for some public inputs.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn validate_ic<P: Parser>(vk: &VerifyingKey<P>) -> std::result::Result<(), EigenError> {
+    use franklin_crypto::bellman::EncodedPoint;
+    for (i, ic) in vk.ic.iter().enumerate() {
+        if ic.is_zero() {
+            return Err(EigenError::InvalidVerifyingKey(format!(
+                "IC[{}] is the identity",
+                i
+            )));
+        }
+        // decoding the uncompressed encoding checks the point is on the curve and in the
+        // subgroup
+        if let Err(e) = ic.into_uncompressed().into_affine() {
+            return Err(EigenError::InvalidVerifyingKey(format!("IC[{}]: {}", i, e)));
+        }
+        if let Some(j) = vk.ic[..i].iter().position(|other| other == ic) {
+            return Err(EigenError::InvalidVerifyingKey(format!(
+                "IC[{}] duplicates IC[{}]",
+                i, j
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Parse `value`, either decimal or `0x` followed by exactly the hex digits
+/// `render_scalar_to_str` writes for `F`, rejecting anything not below the modulus.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+fn parse_coordinate<F: PrimeField>(value: &str) -> Result<F> {
+    let hex_len = F::Repr::default().as_ref().len() * 16;
+    let parsed = match value.strip_prefix("0x") {
+        Some(digits) if digits.len() == hex_len => BigUint::from_str_radix(digits, 16).ok(),
+        Some(_) => bail!(
+            "invalid coordinate {:?}, expect 0x followed by {} hex digits",
+            value,
+            hex_len
+        ),
+        None if value.bytes().all(|b| b.is_ascii_digit()) => {
+            BigUint::from_str_radix(value, 10).ok()
+        }
+        None => None,
+    };
+    let modulus = BigUint::from_str_radix(&repr_to_big(F::char()), 10)?;
+    match parsed {
+        Some(v) if v < modulus => F::from_str(&v.to_str_radix(10))
+            .ok_or_else(|| anyhow::anyhow!("invalid coordinate {:?}", value)),
+        Some(_) => bail!("invalid coordinate {:?}, not below the modulus", value),
+        None => bail!("invalid coordinate {:?}", value),
+    }
+}
+
+/// The public inputs of a snarkjs proof bundle, checked to be as many as `vk` expects and
+/// each below the scalar field modulus, so a bad bundle fails here rather than in
+/// verification.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn extract_and_validate_public<P: Parser>(
+    bundle_json: &str,
+    vk: &VerifyingKey<P>,
+) -> std::result::Result<Vec<P::Fr>, EigenError> {
+    let bundle: ProofBundleFile = serde_json::from_str(bundle_json)
+        .map_err(|e| EigenError::InvalidPublicInputs(format!("invalid proof bundle: {}", e)))?;
+    let expected = vk.ic.len().saturating_sub(1);
+    if bundle.public_signals.len() != expected {
+        return Err(EigenError::PublicInputLength {
+            expected,
+            got: bundle.public_signals.len(),
+        });
+    }
+    bundle
+        .public_signals
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            parse_coordinate::<P::Fr>(x)
+                .map_err(|e| EigenError::InvalidPublicInputs(format!("signal {}: {}", i, e)))
+        })
+        .collect()
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+fn check_header<P: Parser>(
+    protocol: &str,
+    curve: &str,
+    invalid: fn(String) -> EigenError,
+) -> std::result::Result<(), EigenError> {
+    if protocol != "groth16" {
+        return Err(invalid(format!("unsupported protocol {:?}", protocol)));
+    }
+    match normalize_curve_name(curve) {
+        Ok(name) if name == P::curve_name() => Ok(()),
+        _ => Err(EigenError::CurveMismatch {
+            expected: P::curve_name().to_string(),
+            found: curve.to_string(),
+        }),
+    }
+}
+
+/// Parse a snarkjs `verification_key.json` for `P`, checking its protocol, curve, that
+/// every coordinate is a canonical `P::Fq` element and every point is on the curve and in
+/// the prime order subgroup.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn to_verification_key<P: Parser>(s: &str) -> std::result::Result<VerifyingKey<P>, EigenError> {
+    parse_verification_key(s, true)
+}
+
+/// `to_verification_key` without the curve and subgroup checks, for trusted keys.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn to_verification_key_unchecked<P: Parser>(
+    s: &str,
+) -> std::result::Result<VerifyingKey<P>, EigenError> {
+    parse_verification_key(s, false)
+}
+
+/// Parse a snarkjs `proof.json` for `P`, with the same checks as `to_verification_key`.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn to_proof<P: Parser>(s: &str) -> std::result::Result<Proof<P>, EigenError> {
+    parse_proof(s, true)
+}
+
+/// `to_proof` without the curve and subgroup checks, for trusted proofs.
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+pub fn to_proof_unchecked<P: Parser>(s: &str) -> std::result::Result<Proof<P>, EigenError> {
+    parse_proof(s, false)
+}
+
+// decoding the uncompressed encoding checks the point is on the curve and in the subgroup
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+fn check_point<G: CurveAffine>(name: &str, p: G) -> std::result::Result<G, EigenError> {
+    use franklin_crypto::bellman::EncodedPoint;
+    p.into_uncompressed()
+        .into_affine()
+        .map_err(|e| EigenError::InvalidPoint(format!("{}: {}", name, e)))
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+fn parse_verification_key<P: Parser>(
+    s: &str,
+    checked: bool,
+) -> std::result::Result<VerifyingKey<P>, EigenError> {
     let vk_file: VerifyingKeyFile =
-        serde_json::from_str(s).expect("Error during deserialization of the JSON data");
+        serde_json::from_str(s).map_err(|e| EigenError::InvalidVerifyingKey(e.to_string()))?;
+    check_header::<P>(
+        &vk_file.protocol,
+        &vk_file.curve,
+        EigenError::InvalidVerifyingKey,
+    )?;
+    let invalid =
+        |name: &str, e: anyhow::Error| EigenError::InvalidVerifyingKey(format!("{}: {}", name, e));
+    let convert_g1 = |name: &str, point: &G1| {
+        let p = P::to_g1(&point.x, &point.y).map_err(|e| invalid(name, e))?;
+        if checked {
+            check_point(name, p)
+        } else {
+            Ok(p)
+        }
+    };
+    let convert_g2 = |name: &str, point: &G2| {
+        let p = P::to_g2(&point.x[0], &point.x[1], &point.y[0], &point.y[1])
+            .map_err(|e| invalid(name, e))?;
+        if checked {
+            check_point(name, p)
+        } else {
+            Ok(p)
+        }
+    };
+
+    Ok(VerifyingKey {
+        alpha_g1: convert_g1("vk_alpha_1", &vk_file.alpha_g1)?,
+        beta_g1: convert_g1("vk_beta_1", &vk_file.beta_g1)?,
+        beta_g2: convert_g2("vk_beta_2", &vk_file.beta_g2)?,
+        gamma_g2: convert_g2("vk_gamma_2", &vk_file.gamma_g2)?,
+        delta_g1: convert_g1("vk_delta_1", &vk_file.delta_g1)?,
+        delta_g2: convert_g2("vk_delta_2", &vk_file.delta_g2)?,
+        ic: vk_file
+            .ic
+            .iter()
+            .enumerate()
+            .map(|(i, ic)| convert_g1(&format!("IC[{}]", i), ic))
+            .collect::<std::result::Result<_, _>>()?,
+    })
+}
+
+#[cfg(not(any(feature = "cuda", feature = "opencl")))]
+fn parse_proof<P: Parser>(s: &str, checked: bool) -> std::result::Result<Proof<P>, EigenError> {
+    let proof: ProofFile =
+        serde_json::from_str(s).map_err(|e| EigenError::InvalidProof(e.to_string()))?;
+    check_header::<P>(&proof.protocol, &proof.curve, EigenError::InvalidProof)?;
+    let invalid =
+        |name: &str, e: anyhow::Error| EigenError::InvalidProof(format!("{}: {}", name, e));
+    let a = P::to_g1(&proof.a.x, &proof.a.y).map_err(|e| invalid("pi_a", e))?;
+    let b = P::to_g2(&proof.b.x[0], &proof.b.x[1], &proof.b.y[0], &proof.b.y[1])
+        .map_err(|e| invalid("pi_b", e))?;
+    let c = P::to_g1(&proof.c.x, &proof.c.y).map_err(|e| invalid("pi_c", e))?;
+    if !checked {
+        return Ok(Proof { a, b, c });
+    }
+    Ok(Proof {
+        a: check_point("pi_a", a)?,
+        b: check_point("pi_b", b)?,
+        c: check_point("pi_c", c)?,
+    })
+}
+
+#[cfg(any(feature = "cuda", feature = "opencl"))]
+pub fn to_verification_key<P: Parser>(s: &str) -> Result<VerifyingKey<P>> {
+    let vk_file: VerifyingKeyFile = serde_json::from_str(s)?;
 
     let convert_g1 = |point: &G1| P::to_g1(&point.x, &point.y);
     let convert_g2 = |point: &G2| P::to_g2(&point.x[0], &point.x[1], &point.y[0], &point.y[1]);
 
-    VerifyingKey {
+    Ok(VerifyingKey {
         alpha_g1: convert_g1(&vk_file.alpha_g1),
         beta_g1: convert_g1(&vk_file.beta_g1),
         beta_g2: convert_g2(&vk_file.beta_g2),
@@ -383,21 +1134,21 @@ pub fn to_verification_key<P: Parser>(s: &str) -> VerifyingKey<P> {
         delta_g1: convert_g1(&vk_file.delta_g1),
         delta_g2: convert_g2(&vk_file.delta_g2),
         ic: vk_file.ic.iter().map(convert_g1).collect(),
-    }
+    })
 }
 
-pub fn to_proof<P: Parser>(s: &str) -> Proof<P> {
-    let proof: ProofFile =
-        serde_json::from_str(s).expect("Error during deserialization of the JSON data");
+#[cfg(any(feature = "cuda", feature = "opencl"))]
+pub fn to_proof<P: Parser>(s: &str) -> Result<Proof<P>> {
+    let proof: ProofFile = serde_json::from_str(s)?;
 
     let convert_g1 = |point: &G1| P::to_g1(&point.x, &point.y);
     let convert_g2 = |point: &G2| P::to_g2(&point.x[0], &point.x[1], &point.y[0], &point.y[1]);
 
-    Proof {
+    Ok(Proof {
         a: convert_g1(&proof.a),
         b: convert_g2(&proof.b),
         c: convert_g1(&proof.c),
-    }
+    })
 }
 
 #[cfg(test)]
@@ -405,6 +1156,29 @@ pub fn to_proof<P: Parser>(s: &str) -> Proof<P> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_compact_bn256_round_trip() {
+        use franklin_crypto::bellman::compact_bn256::{G1Affine, G2Affine};
+        use franklin_crypto::bellman::CurveProjective;
+        let proof = Proof::<CompactBn256> {
+            a: G1Affine::one(),
+            b: G2Affine::one(),
+            c: {
+                let mut c = G1Affine::one().into_projective();
+                c.double();
+                c.into_affine()
+            },
+        };
+        for to_hex in [false, true] {
+            let json = serialize_proof(&proof, "bn128", to_hex).unwrap();
+            assert_eq!(to_proof::<CompactBn256>(&json).unwrap(), proof);
+        }
+        // the same points, written the same way as on `Bn256`
+        let json = serialize_proof(&proof, "bn128", false).unwrap();
+        let bn256 = to_proof::<Bn256>(&json).unwrap();
+        assert_eq!(serialize_proof(&bn256, "bn128", false).unwrap(), json);
+    }
+
     #[test]
     fn test_serialize_vk() {
         let mut reader = std::io::BufReader::with_capacity(
@@ -412,17 +1186,16 @@ mod tests {
             std::fs::File::open("./test-vectors/verification_key.bin").unwrap(),
         );
         let vk_from_bin = VerifyingKey::<Bn256>::read(&mut reader).unwrap();
-        let result = serialize_vk(&vk_from_bin, "BN128", false).unwrap();
-        std::fs::write("./test-vectors/verification_key.json", result)
-            .expect("Unable to write data to file");
-
-        let json_data = std::fs::read_to_string("./test-vectors/verification_key.json")
-            .expect("Unable to read the JSON file");
-        let verifying_key_from_json = to_verification_key::<Bn256>(&json_data);
+        let json_data = serialize_vk(&vk_from_bin, "BN128", false).unwrap();
+        let verifying_key_from_json = to_verification_key::<Bn256>(&json_data).unwrap();
         assert_eq!(
-            vk_from_bin.alpha_g1, verifying_key_from_json.alpha_g1,
+            vk_from_bin, verifying_key_from_json,
             "VerificationKey are not equal"
         );
+
+        let mut mutated = verifying_key_from_json;
+        mutated.gamma_g2 = mutated.delta_g2;
+        assert_ne!(vk_from_bin, mutated);
     }
 
     #[test]
@@ -432,14 +1205,10 @@ mod tests {
             std::fs::File::open("./test-vectors/verification_key_bls12381.bin").unwrap(),
         );
         let vk_from_bin = VerifyingKey::<Bls12>::read(&mut reader).unwrap();
-        let result = serialize_vk(&vk_from_bin, "BLS12381", false).unwrap();
-        std::fs::write("./test-vectors/verification_key_bls12381.json", result)
-            .expect("Unable to write data to file");
-        let json_data = std::fs::read_to_string("./test-vectors/verification_key_bls12381.json")
-            .expect("Unable to read the JSON file");
-        let verifying_key_from_json = to_verification_key::<Bls12>(&json_data);
+        let json_data = serialize_vk(&vk_from_bin, "BLS12381", false).unwrap();
+        let verifying_key_from_json = to_verification_key::<Bls12>(&json_data).unwrap();
         assert_eq!(
-            vk_from_bin.alpha_g1, verifying_key_from_json.alpha_g1,
+            vk_from_bin, verifying_key_from_json,
             "VerificationKey are not equal"
         );
     }
@@ -451,13 +1220,365 @@ mod tests {
             std::fs::File::open("./test-vectors/proof.bin").unwrap(),
         );
         let proof_from_bin = Proof::<Bn256>::read(&mut reader).unwrap();
-        let result = serialize_proof(&proof_from_bin, "BN128", false).unwrap();
-        std::fs::write("./test-vectors/proof.json", result).expect("Unable to write data to file");
+        let json_data = serialize_proof(&proof_from_bin, "BN128", false).unwrap();
+        let proof_from_json = to_proof::<Bn256>(&json_data).unwrap();
+        assert_eq!(proof_from_bin, proof_from_json, "Proofs are not equal");
+
+        let mut mutated = proof_from_json;
+        mutated.c = mutated.a;
+        assert_ne!(proof_from_bin, mutated);
+    }
+
+    #[test]
+    fn test_serialize_empty_input() {
+        use franklin_crypto::bellman::bn256::Fr;
+        assert_eq!(serialize_input::<Fr>(&[], false).unwrap(), "[]");
+        assert!(to_public_input::<Fr>("[]").unwrap().is_empty());
 
-        let json_data = std::fs::read_to_string("./test-vectors/proof.json")
-            .expect("Unable to read the JSON file");
-        let proof_from_json = to_proof::<Bn256>(&json_data);
-        assert_eq!(proof_from_bin.a, proof_from_json.a, "Proofs are not equal");
+        let inputs = vec![Fr::from_str("33").unwrap()];
+        let json = serialize_input(&inputs, false).unwrap();
+        assert_eq!(to_public_input::<Fr>(&json).unwrap(), inputs);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        use franklin_crypto::bellman::bn256::Fr;
+        let vk_json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        let proof_json = std::fs::read_to_string("./test-vectors/proof.json").unwrap();
+        assert!(matches!(
+            to_verification_key::<Bn256>(&vk_json[..vk_json.len() / 2]),
+            Err(EigenError::InvalidVerifyingKey(_))
+        ));
+        assert!(matches!(
+            to_proof::<Bn256>(&proof_json[..proof_json.len() / 2]),
+            Err(EigenError::InvalidProof(_))
+        ));
+        assert!(matches!(
+            to_public_input::<Fr>("[\"1\", "),
+            Err(EigenError::InvalidPublicInputs(_))
+        ));
+
+        match to_verification_key::<Bls12>(&vk_json) {
+            Err(EigenError::CurveMismatch { expected, found }) => {
+                assert_eq!((expected.as_str(), found.as_str()), ("bls12381", "bn128"))
+            }
+            r => panic!("unexpected {:?}", r.map(|_| ())),
+        }
+        let mut proof_file: ProofFile = serde_json::from_str(&proof_json).unwrap();
+        proof_file.protocol = "plonk".to_string();
+        assert!(matches!(
+            to_proof::<Bn256>(&to_string(&proof_file).unwrap()),
+            Err(EigenError::InvalidProof(_))
+        ));
+    }
+
+    #[test]
+    fn test_off_curve_point() {
+        let proof_json = std::fs::read_to_string("./test-vectors/proof.json").unwrap();
+        let mut proof_file: ProofFile = serde_json::from_str(&proof_json).unwrap();
+        proof_file.a.y = "1".to_string();
+        let off_curve = to_string(&proof_file).unwrap();
+        match to_proof::<Bn256>(&off_curve) {
+            Err(EigenError::InvalidPoint(msg)) => assert!(msg.starts_with("pi_a"), "{}", msg),
+            r => panic!("unexpected {:?}", r.map(|_| ())),
+        }
+        assert!(to_proof_unchecked::<Bn256>(&off_curve).is_ok());
+
+        let vk_json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        let mut vk_file: VerifyingKeyFile = serde_json::from_str(&vk_json).unwrap();
+        vk_file.beta_g2.y[1] = "2".to_string();
+        let off_curve = to_string(&vk_file).unwrap();
+        assert!(matches!(
+            to_verification_key::<Bn256>(&off_curve),
+            Err(EigenError::InvalidPoint(_))
+        ));
+        assert!(to_verification_key_unchecked::<Bn256>(&off_curve).is_ok());
+    }
+
+    #[test]
+    fn test_serialize_decimal() {
+        let mut reader = std::io::BufReader::with_capacity(
+            1 << 24,
+            std::fs::File::open("./test-vectors/proof.bin").unwrap(),
+        );
+        let proof = Proof::<Bn256>::read(&mut reader).unwrap();
+        let expected: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string("./test-vectors/proof_snarkjs.json").unwrap(),
+        )
+        .unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&serialize_proof_decimal(&proof).unwrap()).unwrap();
+        assert_eq!(json, expected);
+        let proof_file: SnarkjsProofFile = serde_json::from_value(json).unwrap();
+        assert_eq!(proof_file.pi_b[2], ["1", "0"]);
+
+        let mut reader = std::io::BufReader::with_capacity(
+            1 << 24,
+            std::fs::File::open("./test-vectors/verification_key.bin").unwrap(),
+        );
+        let vk = VerifyingKey::<Bn256>::read(&mut reader).unwrap();
+        let vk_file: SnarkjsVerifyingKeyFile =
+            serde_json::from_str(&serialize_vk_decimal(&vk).unwrap()).unwrap();
+        assert_eq!(vk_file.curve, "bn128");
+        assert_eq!(vk_file.n_public, vk.ic.len() - 1);
+        assert_eq!(vk_file.ic.len(), vk.ic.len());
+        assert!(vk_file.ic.iter().all(|p| p[2] == "1"));
+        assert_eq!(vk_file.vk_beta_2[2], ["1", "0"]);
+    }
+
+    #[test]
+    fn test_snarkjs_projective_points() {
+        let snarkjs_json = std::fs::read_to_string("./test-vectors/proof_snarkjs.json").unwrap();
+        let mut reader = std::io::BufReader::with_capacity(
+            1 << 24,
+            std::fs::File::open("./test-vectors/proof.bin").unwrap(),
+        );
+        let proof = Proof::<Bn256>::read(&mut reader).unwrap();
+        assert_eq!(to_proof::<Bn256>(&snarkjs_json).unwrap(), proof);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&serialize_proof(&proof, "bn128", false).unwrap()).unwrap();
+        let snarkjs: serde_json::Value = serde_json::from_str(&snarkjs_json).unwrap();
+        if cfg!(feature = "snarkjs_compat") {
+            assert_eq!(json, snarkjs);
+        } else {
+            assert_eq!(json["pi_a"]["x"], snarkjs["pi_a"][0]);
+            assert_eq!(json["pi_b"]["y"], snarkjs["pi_b"][1]);
+        }
+
+        // z must be 1, or 0 for the identity
+        let mut bad_z = snarkjs.clone();
+        bad_z["pi_a"][2] = "2".into();
+        assert!(to_proof::<Bn256>(&bad_z.to_string()).is_err());
+        bad_z["pi_b"][2] = serde_json::json!(["1", "1"]);
+        assert!(serde_json::from_value::<G2>(bad_z["pi_b"].clone()).is_err());
+        let identity: G1 = serde_json::from_value(serde_json::json!(["5", "7", "0"])).unwrap();
+        assert_eq!((identity.x.as_str(), identity.y.as_str()), ("0", "0"));
+    }
+
+    #[test]
+    fn test_identity_round_trip() {
+        use franklin_crypto::bellman::bn256::{G1Affine, G2Affine};
+        let proof = Proof::<Bn256> {
+            a: G1Affine::zero(),
+            b: G2Affine::zero(),
+            c: G1Affine::one(),
+        };
+        for to_hex in [false, true] {
+            let json = serialize_proof(&proof, "bn128", to_hex).unwrap();
+            assert_eq!(to_proof::<Bn256>(&json).unwrap(), proof);
+        }
+
+        let json: serde_json::Value =
+            serde_json::from_str(&serialize_proof(&proof, "bn128", false).unwrap()).unwrap();
+        if cfg!(feature = "snarkjs_compat") {
+            assert_eq!(json["pi_a"], serde_json::json!(["0", "1", "0"]));
+            assert_eq!(
+                json["pi_b"],
+                serde_json::json!([["0", "0"], ["1", "0"], ["0", "0"]])
+            );
+        } else {
+            assert_eq!(json["pi_a"], serde_json::json!({"x": "0", "y": "0"}));
+        }
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&serialize_proof_decimal(&proof).unwrap())
+                .unwrap()["pi_a"],
+            serde_json::json!(["0", "1", "0"])
+        );
+
+        // snarkjs's projective identity reads back as the identity too
+        let mut snarkjs = json;
+        snarkjs["pi_a"] = serde_json::json!(["0", "1", "0"]);
+        snarkjs["pi_b"] = serde_json::json!([["0", "0"], ["1", "0"], ["0", "0"]]);
+        assert_eq!(to_proof::<Bn256>(&snarkjs.to_string()).unwrap(), proof);
+    }
+
+    #[test]
+    fn test_normalize_curve_name() {
+        for name in ["bn128", "bn254", "BN128", "altbn128", "alt_bn128"] {
+            assert_eq!(normalize_curve_name(name).unwrap(), "bn128");
+        }
+        assert_eq!(normalize_curve_name("BLS12381").unwrap(), "bls12381");
+        assert_eq!(normalize_curve_name("bls12-381").unwrap(), "bls12381");
+        assert!(normalize_curve_name("secp256k1").is_err());
+    }
+
+    #[test]
+    fn test_vk_partial_eq() {
+        let mut reader = std::io::BufReader::with_capacity(
+            1 << 24,
+            std::fs::File::open("./test-vectors/verification_key.bin").unwrap(),
+        );
+        let vk = VerifyingKey::<Bn256>::read(&mut reader).unwrap();
+        let decimal =
+            to_verification_key::<Bn256>(&serialize_vk(&vk, "bn128", false).unwrap()).unwrap();
+        let hex = to_verification_key::<Bn256>(&serialize_vk(&vk, "bn128", true).unwrap()).unwrap();
+        assert_eq!(vk, decimal);
+        assert_eq!(decimal, hex);
+
+        let mut modified = decimal;
+        modified.delta_g1 = modified.alpha_g1;
+        assert_ne!(vk, modified);
+        let mut truncated = hex;
+        truncated.ic.pop();
+        assert_ne!(vk, truncated);
+    }
+
+    #[test]
+    fn test_check_coordinates() {
+        let proof_json = std::fs::read_to_string("./test-vectors/proof.json").unwrap();
+        to_proof::<Bn256>(&proof_json).unwrap();
+
+        let mut proof_file: ProofFile = serde_json::from_str(&proof_json).unwrap();
+        proof_file.b.x[0] = "0x1234".to_string();
+        let err = to_proof::<Bn256>(&to_string(&proof_file).unwrap()).unwrap_err();
+        assert!(
+            err.to_string().contains("pi_b: invalid coordinate"),
+            "{}",
+            err
+        );
+        proof_file.b.x[0] = "0x".to_string();
+        assert!(to_proof::<Bn256>(&to_string(&proof_file).unwrap()).is_err());
+        proof_file.b.x[0] = "-1".to_string();
+        assert!(to_proof::<Bn256>(&to_string(&proof_file).unwrap()).is_err());
+
+        let vk_json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        to_verification_key::<Bn256>(&vk_json).unwrap();
+        let mut vk_file: VerifyingKeyFile = serde_json::from_str(&vk_json).unwrap();
+        // the bn128 base field modulus
+        vk_file.delta_g2.y[1] =
+            "21888242871839275222246405745257275088696311157297823662689037894645226208583"
+                .to_string();
+        let err = to_verification_key::<Bn256>(&to_string(&vk_file).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("not below the modulus"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_ic() {
+        use franklin_crypto::bellman::bn256::G1Affine;
+        let mut reader = std::io::BufReader::with_capacity(
+            1 << 24,
+            std::fs::File::open("./test-vectors/verification_key.bin").unwrap(),
+        );
+        let vk = VerifyingKey::<Bn256>::read(&mut reader).unwrap();
+        validate_ic(&vk).unwrap();
+
+        let mut zero_ic = vk.clone();
+        zero_ic.ic[0] = G1Affine::zero();
+        match validate_ic(&zero_ic) {
+            Err(EigenError::InvalidVerifyingKey(msg)) => assert!(msg.contains("IC[0]")),
+            r => panic!("unexpected {:?}", r),
+        }
+
+        let mut duplicated = vk.clone();
+        duplicated.ic.push(vk.ic[0]);
+        assert!(validate_ic(&duplicated).is_err());
+    }
+
+    #[test]
+    fn test_render_scalar_le() {
+        use franklin_crypto::bellman::bn256::Fr;
+        let el = Fr::from_str("1234567890123456789").unwrap();
+        let be = render_scalar_to_str(&el, true);
+        let le = render_scalar_to_hex_le(&el);
+        let mut be_bytes = hex::decode(&be[2..]).unwrap();
+        be_bytes.reverse();
+        assert_eq!(hex::decode(&le[2..]).unwrap(), be_bytes);
+        assert_eq!(swap_hex_endianness(&be), le);
+        assert_eq!(swap_hex_endianness(&le), be);
+        assert_eq!(swap_hex_endianness("0x102"), "0x0201");
+
+        assert_eq!(
+            render_hex_to_scalar::<Fr>(&be, false, Endianness::Big).unwrap(),
+            el
+        );
+        assert_eq!(
+            render_hex_to_scalar::<Fr>(&le, false, Endianness::Little).unwrap(),
+            el
+        );
+        assert_eq!(
+            render_hex_to_scalar::<Fr>("0x01", false, Endianness::Little).unwrap(),
+            Fr::from_str("1").unwrap()
+        );
+        assert_eq!(
+            render_hex_to_scalar::<Fr>("0x102", false, Endianness::Big).unwrap(),
+            Fr::from_str("258").unwrap()
+        );
+        // little-endian must be whole bytes
+        assert!(render_hex_to_scalar::<Fr>("0x102", false, Endianness::Little).is_err());
+        // the modulus isn't canonical
+        let modulus = "0x010000f093f5e1439170b97948e833285d588181b64550b829a031e1724e6430";
+        assert!(render_hex_to_scalar::<Fr>(modulus, false, Endianness::Little).is_err());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let vk_json = std::fs::read_to_string("./test-vectors/verification_key.json").unwrap();
+        let proof_json = std::fs::read_to_string("./test-vectors/proof.json").unwrap();
+        let vk = to_verification_key::<Bn256>(&vk_json).unwrap();
+        let proof = to_proof::<Bn256>(&proof_json).unwrap();
+        for little_endian in [false, true] {
+            let json = serialize_vk_hex(&vk, "bn128", little_endian).unwrap();
+            assert_eq!(
+                to_verification_key_hex::<Bn256>(&json, little_endian).unwrap(),
+                vk
+            );
+            let json = serialize_proof_hex(&proof, "bn128", little_endian).unwrap();
+            assert_eq!(to_proof_hex::<Bn256>(&json, little_endian).unwrap(), proof);
+        }
+    }
+
+    #[test]
+    fn test_render_hex_to_scalar() {
+        use algebraic::Field;
+        use franklin_crypto::bellman::bn256::Fr;
+        let el = Fr::from_str("1234567890123456789").unwrap();
+        let hex = render_scalar_to_str(&el, true);
+        assert_eq!(
+            render_hex_to_scalar::<Fr>(&hex, false, Endianness::Big).unwrap(),
+            el
+        );
+        assert_eq!(
+            render_hex_to_scalar::<Fr>("0x112210f47de98115", false, Endianness::Big).unwrap(),
+            el
+        );
+
+        // missing prefix
+        assert!(matches!(
+            render_hex_to_scalar::<Fr>(&hex[2..], false, Endianness::Big),
+            Err(EigenError::InvalidScalar(_))
+        ));
+        assert_eq!(
+            render_hex_to_scalar::<Fr>(&hex[2..], true, Endianness::Big).unwrap(),
+            el
+        );
+        // non hex chars
+        for value in ["0x12g4", "0x", "0x+1", "0x 1"] {
+            assert!(matches!(
+                render_hex_to_scalar::<Fr>(value, true, Endianness::Big),
+                Err(EigenError::InvalidScalar(_))
+            ));
+        }
+        // the modulus and above
+        let modulus = "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000001";
+        let above = "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000002";
+        for value in [modulus, above] {
+            assert!(matches!(
+                render_hex_to_scalar::<Fr>(value, false, Endianness::Big),
+                Err(EigenError::InvalidScalar(_))
+            ));
+        }
+        let mut minus_one = Fr::one();
+        minus_one.negate();
+        assert_eq!(
+            render_hex_to_scalar::<Fr>(
+                "0x30644e72e131a029b85045b68181585d2833e84879b9709143e1f593f0000000",
+                false,
+                Endianness::Big
+            )
+            .unwrap(),
+            minus_one
+        );
     }
 }
 
@@ -467,20 +1588,16 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_serialize_vk_bls12381() {
+    fn test_serialize_vk_bls12381_gpu() {
         let mut reader = std::io::BufReader::with_capacity(
             1 << 24,
             std::fs::File::open("./test-vectors/verification_key_bls12381.bin").unwrap(),
         );
         let vk_from_bin = VerifyingKey::<Bls12>::read(&mut reader).unwrap();
-        let result = serialize_vk(&vk_from_bin, "BLS12381", false).unwrap();
-        std::fs::write("./test-vectors/verification_key_bls12381.json", result)
-            .expect("Unable to write data to file");
-        let json_data = std::fs::read_to_string("./test-vectors/verification_key_bls12381.json")
-            .expect("Unable to read the JSON file");
-        let verifying_key_from_json = to_verification_key::<Bls12>(&json_data);
+        let json_data = serialize_vk(&vk_from_bin, "BLS12381", false).unwrap();
+        let verifying_key_from_json = to_verification_key::<Bls12>(&json_data).unwrap();
         assert_eq!(
-            vk_from_bin.alpha_g1, verifying_key_from_json.alpha_g1,
+            vk_from_bin, verifying_key_from_json,
             "VerificationKey are not equal"
         );
     }
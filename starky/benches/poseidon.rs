@@ -1,13 +1,25 @@
+//! Poseidon and LinearHashBN128 benchmarks.
+//!
+//! Record a baseline before a change and compare against it afterwards:
+//!
+//! ```text
+//! cargo bench --bench poseidon -- --save-baseline before
+//! cargo bench --bench poseidon -- --baseline before
+//! ```
 #[macro_use]
 extern crate criterion;
 
 use criterion::{BenchmarkId, Criterion};
 use ff::Field;
-use starky::dev::gen_rand_fields;
+use starky::dev::{gen_rand_fields, gen_rand_goldilocks_matrix};
 use starky::field_bn128::Fr;
+use starky::linearhash_bn128::LinearHashBN128;
 use starky::poseidon_bn128_opt::Poseidon;
+use starky::traits::MTNodeType;
+use starky::ElementDigest;
 const MIN_K: usize = 6;
 const MAX_K: usize = 24;
+const TRACE_WIDTHS: [usize; 5] = [4, 16, 64, 256, 1024];
 
 fn bench_poseidon128(c: &mut Criterion) {
     let poseidon = Poseidon::new();
@@ -22,8 +34,39 @@ fn bench_poseidon128(c: &mut Criterion) {
     }
 }
 
+fn bench_linearhash_bn128(c: &mut Criterion) {
+    let lh = LinearHashBN128::new();
+
+    let mut group = c.benchmark_group("linearhash_bn128");
+    for width in TRACE_WIDTHS {
+        let columns = gen_rand_goldilocks_matrix(1, width);
+        group.bench_function(BenchmarkId::new("hash_element_matrix", width), |b| {
+            b.iter(|| lh.hash_element_matrix(&columns).unwrap());
+        });
+    }
+
+    let nodes: Vec<ElementDigest<4, Fr>> = gen_rand_fields::<Fr>(4)
+        .iter()
+        .map(ElementDigest::from_scalar)
+        .collect();
+    let init = Fr::zero();
+    group.bench_function("hash_node", |b| {
+        b.iter(|| lh.hash_node(&nodes, &init).unwrap());
+    });
+
+    let st64: [_; 4] = gen_rand_goldilocks_matrix(1, 4)[0]
+        .clone()
+        .try_into()
+        .unwrap();
+    group.bench_function("to_bn128_mont", |b| {
+        b.iter(|| LinearHashBN128::to_bn128_mont(st64));
+    });
+    group.finish();
+}
+
 fn criterion_benchmark(c: &mut Criterion) {
     bench_poseidon128(c);
+    bench_linearhash_bn128(c);
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -19,7 +19,7 @@ use crate::arch::x86_64::avx2_poseidon_gl::{load_constants_avx2, ConstantsAvx2};
 ))]
 use crate::arch::x86_64::avx512_poseidon_gl::{load_constants_avx512, ConstantsAvx512};
 use crate::field_bls12381::Fr as Fr_bls12381;
-use crate::field_bn128::Fr as Fr_bn128;
+use crate::field_bn128::{Fr as Fr_bn128, FrRepr as FrRepr_bn128};
 use crate::poseidon_bls12381::load_constants as load_constants_bls12381;
 use crate::poseidon_bls12381::Constants as ConstantsBls12381;
 use crate::poseidon_bls12381_opt::load_constants as load_constants_bls12381_opt;
@@ -29,6 +29,16 @@ use ff::*;
 use fields::field_gl::Fr;
 use std::collections::HashMap;
 
+/// `R^2 mod p` for the BN128 scalar field `p`, with the Montgomery radix `R = 2^256`, little
+/// endian limbs. As a raw Montgomery element it stands for `R`, so a Montgomery product with
+/// it turns a plain value `x` into its Montgomery form `x * R mod p`.
+pub const R2_BN128: FrRepr_bn128 = FrRepr_bn128([
+    1997599621687373223u64,
+    6052339484930628067u64,
+    10108755138030829701u64,
+    150537098327114917u64,
+]);
+
 lazy_static::lazy_static! {
     pub static ref OFFSET_2_64: Fr_bn128 = Fr_bn128::from_str("18446744073709551616").unwrap();
     pub static ref OFFSET_2_128: Fr_bn128 = Fr_bn128::from_str("340282366920938463463374607431768211456").unwrap();
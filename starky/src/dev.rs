@@ -1,6 +1,7 @@
 /// A test/bench tools
 use crate::traits::FieldExtension;
 use ff::PrimeField;
+use fields::field_gl::Fr as FGL;
 use rayon::prelude::*;
 
 // concurrency generate random goldfields. with specific k.
@@ -28,6 +29,19 @@ pub fn gen_rand_fields<F: PrimeField>(k: usize) -> Vec<F> {
     parts
 }
 
+// concurrency generate a random goldilocks matrix with `rows` rows and `cols` columns.
+pub fn gen_rand_goldilocks_matrix(rows: usize, cols: usize) -> Vec<Vec<FGL>> {
+    (0..rows)
+        .into_par_iter()
+        .map(|_| {
+            let mut rng = ::rand::thread_rng();
+            (0..cols)
+                .map(|_| <FGL as rand::Rand>::rand(&mut rng))
+                .collect()
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -49,4 +63,11 @@ mod test {
         let res = gen_rand_fields::<Fr>(k);
         assert_eq!(n, res.len());
     }
+
+    #[test]
+    fn test_gen_rand_goldilocks_matrix() {
+        let res = gen_rand_goldilocks_matrix(3, 5);
+        assert_eq!(res.len(), 3);
+        assert!(res.iter().all(|row| row.len() == 5));
+    }
 }
@@ -4,6 +4,7 @@ use crate::field_bls12381::FrRepr as FrRepr_bls12381;
 use crate::field_bn128::{Fr, FrRepr};
 use crate::helper;
 use crate::traits::MTNodeType;
+use anyhow::{anyhow, Result};
 use ff::*;
 use fields::field_gl::Fr as FGL;
 use serde::de::{SeqAccess, Visitor};
@@ -27,6 +28,23 @@ impl<const N: usize, F: PrimeField + Default> ElementDigest<N, F> {
     }
 }
 
+impl<F: PrimeField + Default> ElementDigest<4, F> {
+    /// The canonical limbs of the digest, in the order of plonky2's `HashOut::elements`.
+    pub fn to_hash_out(&self) -> [u64; 4] {
+        self.0.map(|e| e.as_int())
+    }
+
+    /// The digest of a plonky2 `HashOut`, given the `to_canonical_u64` of its elements.
+    pub fn from_hash_out(elements: [u64; 4]) -> Result<Self> {
+        let mut fv = [FGL::ZERO; 4];
+        for (e, v) in fv.iter_mut().zip(elements.iter()) {
+            *e = FGL::from_repr(fields::field_gl::FrRepr::from(*v))
+                .map_err(|_| anyhow!("hash out element {} is not a canonical goldilocks", v))?;
+        }
+        Ok(Self(fv, Default::default()))
+    }
+}
+
 impl<const N: usize, F: PrimeField + Default> MTNodeType for ElementDigest<N, F> {
     type BaseField = F;
     #[inline(always)]
@@ -312,6 +330,25 @@ mod tests {
         assert_eq!(expected, e1);
     }
 
+    #[test]
+    fn test_hash_out() {
+        let limbs = [1u64, 0xFFFFFFFF00000000, 42, 0x1234567890abcdef];
+        let digest = ElementDigest::<4, FGL>::from_hash_out(limbs).unwrap();
+        assert_eq!(digest.as_elements()[1], FGL::from(0xFFFFFFFF00000000u64));
+        assert_eq!(digest.to_hash_out(), limbs);
+
+        let mut rng = ::rand::thread_rng();
+        let fields = (0..4).map(|_| FGL::rand(&mut rng)).collect::<Vec<_>>();
+        let digest = ElementDigest::<4, Fr>::new(&fields);
+        assert_eq!(
+            ElementDigest::<4, Fr>::from_hash_out(digest.to_hash_out()).unwrap(),
+            digest
+        );
+
+        // the goldilocks modulus itself is out of range
+        assert!(ElementDigest::<4, FGL>::from_hash_out([0xFFFFFFFF00000001, 0, 0, 0]).is_err());
+    }
+
     #[test]
     fn test_element_digest_serialize_and_deserialize() {
         const N: usize = 4;
@@ -1,14 +1,15 @@
 #![allow(non_snake_case)]
+use crate::constant::{OFFSET_2_128, OFFSET_2_64, R2_BN128};
 use crate::field_bn128::{Fr, FrRepr};
 use crate::poseidon_bn128_opt::Poseidon;
-use crate::traits::MTNodeType;
+use crate::traits::{FieldExtension, MTNodeType};
 use crate::ElementDigest;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use ff::*;
-use serde::{Deserialize, Serialize};
-//use rayon::prelude::*;
-use crate::constant::{OFFSET_2_128, OFFSET_2_64};
 use fields::field_gl::Fr as FGL;
+#[cfg(feature = "parallel-hash")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Default, Debug, PartialEq, Serialize, Deserialize)]
 pub struct LinearHashBN128 {
@@ -20,8 +21,10 @@ impl LinearHashBN128 {
         LinearHashBN128 { h: Poseidon::new() }
     }
 
-    pub fn hash_element_matrix(&self, columns: &[Vec<FGL>]) -> Result<Fr> {
-        let mut st = Fr::zero();
+    // pack the elements of all the columns, in order, three by three into `e0 + e1 * 2^64 +
+    // e2 * 2^128`, the last pack possibly shorter
+    #[cfg_attr(feature = "parallel-hash", allow(dead_code))]
+    fn pack_triples(columns: &[Vec<FGL>]) -> Result<Vec<Fr>> {
         let mut vals3: Vec<Fr> = vec![];
 
         let mut acc = Fr::zero();
@@ -47,43 +50,87 @@ impl LinearHashBN128 {
         if accN > 0 {
             vals3.push(acc);
         }
+        Ok(vals3)
+    }
+
+    // same as `pack_triples`, each pack computed independently
+    #[cfg(feature = "parallel-hash")]
+    fn pack_triples_parallel(columns: &[Vec<FGL>]) -> Result<Vec<Fr>> {
+        let elems = columns.iter().flatten().collect::<Vec<_>>();
+        elems
+            .par_chunks(3)
+            .map(|triple| {
+                let mut acc = Fr::zero();
+                for (i, elem) in triple.iter().enumerate() {
+                    let mut e = Fr::from_repr(FrRepr::from(elem.as_int()))?;
+                    if i == 1 {
+                        e.mul_assign(&OFFSET_2_64);
+                    } else if i == 2 {
+                        e.mul_assign(&OFFSET_2_128);
+                    }
+                    acc.add_assign(&e);
+                }
+                Ok(acc)
+            })
+            .collect()
+    }
+
+    pub fn hash_element_matrix(&self, columns: &[Vec<FGL>]) -> Result<Fr> {
+        let st = Fr::zero();
+        #[cfg(not(feature = "parallel-hash"))]
+        let vals3 = Self::pack_triples(columns)?;
+        #[cfg(feature = "parallel-hash")]
+        let vals3 = Self::pack_triples_parallel(columns)?;
         if vals3.is_empty() {
             return Ok(st);
         } else if vals3.len() == 1 {
             return Ok(vals3[0]);
         }
-        let mut inHash: Vec<Fr> = vec![];
+        self.hash_fr(&vals3, &st)
+    }
 
-        for val3 in vals3.iter() {
-            inHash.push(*val3);
-            if inHash.len() == 16 {
-                st = self.h.hash(&inHash, &st)?;
-                inHash = vec![];
-            }
-        }
-        if !inHash.is_empty() {
-            st = self.h.hash(&inHash, &st)?;
+    /// Poseidon hash of native BN128 elements: `inputs` is cut into chunks of 16, the last
+    /// one possibly shorter and not padded, and each chunk is hashed with the previous
+    /// chunk's hash as the initial state, `init_state` for the first. No input hashes to
+    /// `init_state`.
+    pub fn hash_fr(&self, inputs: &[Fr], init_state: &Fr) -> Result<Fr> {
+        let mut st = *init_state;
+        for chunk in inputs.chunks(16) {
+            st = self.h.hash(chunk, &st)?;
         }
         Ok(st)
     }
 
+    /// Same as `hash_element_matrix` for columns holding extension field elements. Every
+    /// element is decomposed into its `dim()` base components, lowest degree first, and the
+    /// components take its place in the column before grouping into threes, so a `dim() == 1`
+    /// element hashes exactly like the base element.
+    pub fn hash_element_matrix_ext<F: FieldExtension>(&self, columns: &[Vec<F>]) -> Result<Fr> {
+        let columns = columns
+            .iter()
+            .map(|col| {
+                col.iter()
+                    .flat_map(|e| e.as_elements())
+                    .collect::<Vec<FGL>>()
+            })
+            .collect::<Vec<_>>();
+        self.hash_element_matrix(&columns)
+    }
+
     /// convert to BN128 in montgomery
+    ///
+    /// The four limbs make a 256-bit `x` which may exceed the modulus `p`, `from_repr`
+    /// rejects it then. In that corner case `x` is taken as a raw Montgomery element and
+    /// multiplied by the raw `R2_BN128`, the Montgomery product `x * R^2 * R^-1` being
+    /// `(x mod p) * R`, the Montgomery form of `x mod p`.
     #[inline(always)]
     pub fn to_bn128_mont(st64: [FGL; 4]) -> [FGL; 4] {
         let bn: Fr = Fr(ElementDigest::<4, Fr>::new(&st64).as_scalar::<Fr>());
         let bn_mont = match Fr::from_repr(bn.into_raw_repr()) {
             Ok(x) => x,
             _ => {
-                //cornor case: x > MODULUS
                 let mut r = Fr(bn.into_raw_repr());
-                // 2^128 mod p.
-                const R2: FrRepr = FrRepr([
-                    1997599621687373223u64,
-                    6052339484930628067u64,
-                    10108755138030829701u64,
-                    150537098327114917u64,
-                ]);
-                r.mul_assign(&Fr(R2));
+                r.mul_assign(&Fr(R2_BN128));
                 r
             }
         };
@@ -93,22 +140,35 @@ impl LinearHashBN128 {
             .unwrap()
     }
 
+    /// Hash up to 16 children of a Merkle tree node. A partial node, at the tree boundary,
+    /// is padded with zeros, so it hashes like the full node with zero children.
     #[inline(always)]
     pub fn hash_node(
         &self,
         elems: &[ElementDigest<4, Fr>],
         init_state: &Fr,
     ) -> Result<ElementDigest<4, Fr>> {
-        assert_eq!(elems.len(), 16);
-        let elems = elems
+        if elems.is_empty() || elems.len() > 16 {
+            bail!("hash_node takes 1 to 16 elements, got {}", elems.len());
+        }
+        let mut elems = elems
             .iter()
             .map(|e| Fr((*e).as_scalar::<Fr>()))
             .collect::<Vec<Fr>>();
+        elems.resize(16, Fr::zero());
         let digest = self.h.hash(&elems, init_state)?;
         Ok(ElementDigest::<4, Fr>::from_scalar(&digest))
     }
 
     pub fn hash_element_array(&self, vals: &[FGL]) -> Result<ElementDigest<4, Fr>> {
+        Ok(ElementDigest::<4, Fr>::from_scalar(
+            &self.hash_element_array_fr(vals)?,
+        ))
+    }
+
+    /// Same as `hash_element_array`, returning the digest as a BN128 element. Up to 4
+    /// values aren't hashed, the element is the one whose raw limbs are the digest's.
+    pub fn hash_element_array_fr(&self, vals: &[FGL]) -> Result<Fr> {
         let mut st64 = [FGL::ZERO; 4];
         let mut digest: Fr = Fr::zero();
         if vals.len() <= 4 {
@@ -116,7 +176,7 @@ impl LinearHashBN128 {
                 st64[i] = *v;
             }
             let gl_mont = Self::to_bn128_mont(st64);
-            return Ok(ElementDigest::<4, Fr>::new(&gl_mont));
+            return Ok(Fr(ElementDigest::<4, Fr>::new(&gl_mont).as_scalar::<Fr>()));
         }
 
         // group into 3 * 4
@@ -136,7 +196,7 @@ impl LinearHashBN128 {
             digest = self.h.hash(&tmp_buf[i..(i + in_sz)], &digest)?;
         }
 
-        Ok(ElementDigest::<4, Fr>::from_scalar(&digest))
+        Ok(digest)
     }
 }
 
@@ -160,6 +220,147 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg(feature = "parallel-hash")]
+    fn test_pack_triples_parallel() {
+        let inputs: Vec<Vec<FGL>> = (0..100u64)
+            .map(|e| vec![FGL::from(e), FGL::from(e * 1000), FGL::from(e * 1000000)])
+            .collect();
+        // triples straddling columns, and a short last one
+        let mut ragged = inputs.clone();
+        ragged[7].pop();
+        ragged[42].truncate(1);
+        for columns in [inputs, ragged] {
+            assert_eq!(
+                LinearHashBN128::pack_triples_parallel(&columns).unwrap(),
+                LinearHashBN128::pack_triples(&columns).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_linearhash_matrix_ext_bn128() {
+        use crate::f3g::F3G;
+        let ext = |a: u64, b: u64, c: u64| F3G::new(FGL::from(a), FGL::from(b), FGL::from(c));
+        let trace = vec![
+            vec![F3G::from(FGL::from(7u64)), ext(1, 2, 3), ext(4, 5, 6)],
+            vec![F3G::from(FGL::from(8u64)), ext(7, 8, 9), ext(10, 11, 12)],
+        ];
+        let decomposed = vec![
+            [7u64, 1, 2, 3, 4, 5, 6].map(FGL::from).to_vec(),
+            [8u64, 7, 8, 9, 10, 11, 12].map(FGL::from).to_vec(),
+        ];
+
+        let lh = LinearHashBN128::new();
+        let st = lh.hash_element_matrix_ext(&trace).unwrap();
+        assert_eq!(st, lh.hash_element_matrix(&decomposed).unwrap());
+        assert_eq!(st, lh.hash_element_matrix_ext(&trace).unwrap());
+
+        let mut swapped = trace.clone();
+        swapped[0][1] = ext(3, 2, 1);
+        assert_ne!(st, lh.hash_element_matrix_ext(&swapped).unwrap());
+    }
+
+    #[test]
+    fn test_hash_fr() {
+        use crate::field_bn128::Fr;
+        use ff::{Field, PrimeField};
+
+        let inputs: Vec<Vec<FGL>> = (0..100u64)
+            .map(|e| vec![FGL::from(e), FGL::from(e * 1000), FGL::from(e * 1000000)])
+            .collect();
+        let vals3 = LinearHashBN128::pack_triples(&inputs).unwrap();
+        let lh = LinearHashBN128::new();
+        assert_eq!(
+            lh.hash_fr(&vals3, &Fr::zero()).unwrap().to_string(),
+            "Fr(0x29c2ac38b7b8d18b9c1b575369cb4ab930ef71ebd5e4631b3916360233a29cae)",
+        );
+
+        let inputs = (1..=20u64)
+            .map(|i| Fr::from_str(&i.to_string()).unwrap())
+            .collect::<Vec<_>>();
+        let init = Fr::from_str("7").unwrap();
+        let first = lh.h.hash(&inputs[..16], &init).unwrap();
+        assert_eq!(
+            lh.hash_fr(&inputs, &init).unwrap(),
+            lh.h.hash(&inputs[16..], &first).unwrap()
+        );
+        assert_eq!(lh.hash_fr(&[], &init).unwrap(), init);
+    }
+
+    #[test]
+    fn test_hash_node_partial() {
+        use crate::field_bn128::Fr;
+        use crate::traits::MTNodeType;
+        use crate::ElementDigest;
+        use ff::Field;
+
+        let lh = LinearHashBN128::new();
+        let nodes = (1..=17u64)
+            .map(|i| ElementDigest::<4, Fr>::new(&[FGL::from(i), FGL::ZERO, FGL::ZERO, FGL::ZERO]))
+            .collect::<Vec<_>>();
+        let zero = ElementDigest::<4, Fr>::from_scalar(&Fr::zero());
+        for len in [1, 8, 15] {
+            let mut padded = nodes[..len].to_vec();
+            padded.resize(16, zero);
+            assert_eq!(
+                lh.hash_node(&nodes[..len], &Fr::zero()).unwrap(),
+                lh.hash_node(&padded, &Fr::zero()).unwrap()
+            );
+        }
+        assert_ne!(
+            lh.hash_node(&nodes[..8], &Fr::zero()).unwrap(),
+            lh.hash_node(&nodes[..9], &Fr::zero()).unwrap()
+        );
+        assert!(lh.hash_node(&nodes, &Fr::zero()).is_err());
+        assert!(lh.hash_node(&[], &Fr::zero()).is_err());
+    }
+
+    #[test]
+    fn test_to_bn128_mont_above_modulus() {
+        use crate::field_bn128::Fr;
+        use crate::traits::MTNodeType;
+        use crate::ElementDigest;
+        use ff::PrimeField;
+
+        let mont = |v: &str| {
+            ElementDigest::<4, Fr>::from_scalar(&Fr::from_str(v).unwrap())
+                .as_elements()
+                .to_vec()
+        };
+        // the largest goldilocks in every limb, x = 0xffffffff00000000... > p
+        let st64 = [FGL::from(0xFFFFFFFF00000000u64); 4];
+        assert_eq!(
+            LinearHashBN128::to_bn128_mont(st64).to_vec(),
+            // x mod p
+            mont("6350874851159872651465418221659696450353700861768939782779620032646217727995")
+        );
+
+        let st64 = [FGL::from(5u64), FGL::from(1u64), FGL::ZERO, FGL::ZERO];
+        assert_eq!(
+            LinearHashBN128::to_bn128_mont(st64).to_vec(),
+            mont("18446744073709551621")
+        );
+    }
+
+    #[test]
+    fn test_hash_element_array_fr() {
+        use crate::field_bn128::Fr;
+        use crate::traits::MTNodeType;
+
+        let lh = LinearHashBN128::new();
+        for len in [0u64, 2, 4, 5, 60] {
+            let vals = (0..len)
+                .map(|i| FGL::from(i * 0x1234567 + 18440682777423237490))
+                .collect::<Vec<_>>();
+            let digest = lh.hash_element_array(&vals).unwrap();
+            assert_eq!(
+                lh.hash_element_array_fr(&vals).unwrap(),
+                Fr(digest.as_scalar::<Fr>())
+            );
+        }
+    }
+
     #[test]
     fn test_linearhash_corner_case() {
         let input = vec![
@@ -132,6 +132,38 @@ impl MerkleTreeBN128 {
         self.merkle_calculate_root_from_proof(mp, next_idx, &next_value, offset + 1)
     }
 
+    /// Replace the leaf at `idx` and rehash only the path up to the root, returning the new root.
+    pub fn update_leaf(&mut self, idx: usize, new_leaf: &[FGL]) -> Result<ElementDigest<4, Fr>> {
+        if idx >= self.height {
+            bail!("MerkleTreeError: access invalid node");
+        }
+        if new_leaf.len() != self.width {
+            bail!(
+                "MerkleTreeError: invalid leaf width {}, expected {}",
+                new_leaf.len(),
+                self.width
+            );
+        }
+        self.elements[(idx * self.width)..((idx + 1) * self.width)].copy_from_slice(new_leaf);
+        self.nodes[idx] = self.h.hash_element_array(new_leaf)?;
+
+        let mut idx = idx;
+        let mut n = self.height;
+        let mut offset = 0;
+        while n > 1 {
+            let next_n = (n - 1) / 16 + 1;
+            let si = idx & !0xF;
+            let parent = self
+                .h
+                .hash_node(&self.nodes[(offset + si)..(offset + si + 16)], &Fr::zero())?;
+            offset += next_n * 16;
+            idx >>= 4;
+            self.nodes[offset + idx] = parent;
+            n = next_n;
+        }
+        Ok(self.root())
+    }
+
     fn calculate_root_from_group_proof(
         &self,
         mp: &[Vec<Fr>],
@@ -375,4 +407,32 @@ mod tests {
         let expect: MerkleTreeBN128 = serde_json::from_str(&serialized).unwrap();
         assert_eq!(data, expect);
     }
+
+    #[test]
+    fn test_merkle_tree_bn128_update_leaf() {
+        let n = 300;
+        let n_pols = 5;
+        let idx = 37;
+        let mut cols: Vec<FGL> = (0..(n * n_pols)).map(|i| FGL::from(i as u64)).collect();
+
+        let mut tree = MerkleTreeBN128::new();
+        tree.merkelize(cols.clone(), n_pols, n).unwrap();
+        let old_root = tree.root();
+
+        let new_leaf = (0..n_pols)
+            .map(|j| FGL::from((j + 100000) as u64))
+            .collect::<Vec<_>>();
+        let root = tree.update_leaf(idx, &new_leaf).unwrap();
+        assert_ne!(root, old_root);
+
+        cols[(idx * n_pols)..((idx + 1) * n_pols)].copy_from_slice(&new_leaf);
+        let mut rebuilt = MerkleTreeBN128::new();
+        rebuilt.merkelize(cols, n_pols, n).unwrap();
+        assert_eq!(root, rebuilt.root());
+        assert_eq!(tree.nodes, rebuilt.nodes);
+
+        let (v, mp) = tree.get_group_proof(idx).unwrap();
+        assert!(tree.verify_group_proof(&root, &mp, idx, &v).unwrap());
+        assert!(tree.update_leaf(n, &new_leaf).is_err());
+    }
 }
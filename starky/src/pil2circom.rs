@@ -28,7 +28,7 @@ pub fn pil2circom<F: ff::PrimeField + Default>(
 ) -> Result<String> {
     starkinfo.set_code_dimensions_first(&mut program.verifier_code)?;
     starkinfo.set_code_dimensions_first(&mut program.verifier_query_code)?;
-    let res = match stark_struct.verificationHashType.as_str() {
+    let res = match stark_struct.hash_type()? {
         "GL" => crate::stark_verifier_circom::render(
             starkinfo,
             program,
@@ -45,7 +45,7 @@ pub fn pil2circom<F: ff::PrimeField + Default>(
             const_root,
             options,
         ),
-        _ => panic!("Invalid hash type: {}", stark_struct.verificationHashType),
+        _ => unreachable!(),
     };
     Ok(res)
 }
@@ -49,7 +49,7 @@ pub fn stark_prove(
     let stark_struct = load_json::<StarkStruct>(stark_struct)?;
     let circom_file_writer = File::create(circom_file)?;
     let zkin_writer = File::create(zkin)?;
-    match stark_struct.verificationHashType.as_str() {
+    match stark_struct.hash_type()? {
         "BN128" => prove::<Fr_BN128, MerkleTreeBN128, TranscriptBN128, _>(
             &mut pil,
             const_pol,
@@ -86,7 +86,7 @@ pub fn stark_prove(
             zkin_writer,
             prover_addr,
         ),
-        _ => panic!("Invalid hashtype {}", stark_struct.verificationHashType),
+        _ => unreachable!(),
     }
 }
 
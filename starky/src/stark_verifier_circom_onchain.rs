@@ -158,9 +158,9 @@ impl Transcript {
         let totalBits = n * nBits;
         let NFields = (totalBits - 1) / 253 + 1;
         let mut n2b: Vec<String> = vec![];
-        let n2bt = match self.stark_struct.verificationHashType.as_str() {
-            "BN128" => "Num2Bits_strict()".to_string(),
-            "BLS12381" => "Num2Bits(255)".to_string(),
+        let n2bt = match self.stark_struct.hash_type() {
+            Ok("BN128") => "Num2Bits_strict()".to_string(),
+            Ok("BLS12381") => "Num2Bits(255)".to_string(),
             _ => todo!(),
         };
         for i in 0..NFields {
@@ -9,6 +9,7 @@ use anyhow::Result;
 use ff::*;
 use fields::field_gl::Fr as FGL;
 use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 pub struct TranscriptBN128 {
@@ -19,7 +20,35 @@ pub struct TranscriptBN128 {
     out3: VecDeque<FGL>,
 }
 
+/// Everything absorbed and squeezed so far by a `TranscriptBN128`, to fork the transcript
+/// from a common prefix.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpongeState {
+    state: Fr,
+    pending: Vec<Fr>,
+    out: VecDeque<Fr>,
+    out3: VecDeque<FGL>,
+}
+
 impl TranscriptBN128 {
+    pub fn checkpoint(&self) -> SpongeState {
+        SpongeState {
+            state: self.state,
+            pending: self.pending.clone(),
+            out: self.out.clone(),
+            out3: self.out3.clone(),
+        }
+    }
+
+    /// Resume from `state`, the challenges derived afterwards are the ones the checkpointed
+    /// transcript would have produced.
+    pub fn restore(&mut self, state: SpongeState) {
+        self.state = state.state;
+        self.pending = state.pending;
+        self.out = state.out;
+        self.out3 = state.out3;
+    }
+
     fn update_state(&mut self) -> Result<()> {
         while self.pending.len() < 16 {
             self.pending.push(Fr::zero());
@@ -131,3 +160,59 @@ impl Transcript for TranscriptBN128 {
         Ok(res)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_restore() {
+        let prefix = (1..20u64).map(|i| vec![FGL::from(i)]).collect::<Vec<_>>();
+        let mut transcript = TranscriptBN128::new();
+        transcript.put(&prefix).unwrap();
+        let checkpoint = transcript.checkpoint();
+
+        transcript.put(&[vec![FGL::from(100u64)]]).unwrap();
+        let first = (0..4)
+            .map(|_| transcript.get_fields1().unwrap())
+            .collect::<Vec<_>>();
+
+        transcript.restore(checkpoint.clone());
+        transcript.put(&[vec![FGL::from(200u64)]]).unwrap();
+        let second = (0..4)
+            .map(|_| transcript.get_fields1().unwrap())
+            .collect::<Vec<_>>();
+        assert_ne!(first, second);
+
+        // a restored transcript replays the same stream as a fresh one fed the same data
+        let mut fresh = TranscriptBN128::new();
+        fresh.put(&prefix).unwrap();
+        fresh.put(&[vec![FGL::from(100u64)]]).unwrap();
+        transcript.restore(checkpoint);
+        transcript.put(&[vec![FGL::from(100u64)]]).unwrap();
+        for expected in first {
+            assert_eq!(fresh.get_fields1().unwrap(), expected);
+            assert_eq!(transcript.get_fields1().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_serialize_sponge_state() {
+        let mut transcript = TranscriptBN128::new();
+        transcript
+            .put(&(1..20u64).map(|i| vec![FGL::from(i)]).collect::<Vec<_>>())
+            .unwrap();
+        transcript.get_fields1().unwrap();
+        let json = serde_json::to_string(&transcript.checkpoint()).unwrap();
+
+        let expected = (0..4)
+            .map(|_| transcript.get_fields1().unwrap())
+            .collect::<Vec<_>>();
+
+        let mut restored = TranscriptBN128::new();
+        restored.restore(serde_json::from_str::<SpongeState>(&json).unwrap());
+        for expected in expected {
+            assert_eq!(restored.get_fields1().unwrap(), expected);
+        }
+    }
+}
@@ -6,7 +6,7 @@ use std::fmt;
 use std::fs::File;
 use std::io::Read;
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub struct Public {
@@ -200,6 +200,28 @@ pub struct StarkStruct {
     pub steps: Vec<Step>,
 }
 
+impl StarkStruct {
+    /// `verificationHashType` as `GL`, `BN128` or `BLS12381`, accepting the spellings
+    /// groth16's `normalize_curve_name` does, e.g. `bn254` or `bls12-381`.
+    pub fn hash_type(&self) -> Result<&'static str> {
+        normalize_hash_type(&self.verificationHashType)
+    }
+}
+
+pub fn normalize_hash_type(hash_type: &str) -> Result<&'static str> {
+    let name = hash_type
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .collect::<String>()
+        .to_lowercase();
+    match name.as_str() {
+        "gl" | "goldilocks" => Ok("GL"),
+        "bn128" | "bn254" | "bn256" | "altbn128" => Ok("BN128"),
+        "bls12381" => Ok("BLS12381"),
+        _ => bail!("Invalid hash type: {}", hash_type),
+    }
+}
+
 pub fn load_json<T>(filename: &str) -> Result<T>
 where
     T: serde::de::DeserializeOwned,
@@ -271,6 +293,18 @@ mod test {
         }
         ]
     }"#;
-        read_json::<StarkStruct>(json_str.to_string()).unwrap();
+        let stark_struct = read_json::<StarkStruct>(json_str.to_string()).unwrap();
+        assert_eq!(stark_struct.hash_type().unwrap(), "BN128");
+    }
+
+    #[test]
+    pub fn test_normalize_hash_type() {
+        for name in ["BN128", "bn254", "alt_bn128", "BN256"] {
+            assert_eq!(normalize_hash_type(name).unwrap(), "BN128");
+        }
+        assert_eq!(normalize_hash_type("bls12-381").unwrap(), "BLS12381");
+        assert_eq!(normalize_hash_type("Goldilocks").unwrap(), "GL");
+        assert_eq!(normalize_hash_type("gl").unwrap(), "GL");
+        assert!(normalize_hash_type("secp256k1").is_err());
     }
 }
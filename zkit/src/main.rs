@@ -23,7 +23,7 @@ pub struct CompilierOpt {
     #[arg(long = "O0", hide = false)]
     no_simplification: bool,
 
-    /// prime field, like goldilocks
+    /// prime field, one of bn128, bls12381, goldilocks, grumpkin, pallas or vesta
     #[arg(short, default_value = "BN128")]
     prime: String,
 
@@ -282,7 +282,9 @@ fn main() {
             args.output,
             args.no_simplification,
             args.reduced_simplification,
-        ),
+        )
+        .map(|report| log::info!("compiled with {:?}", report.simplification))
+        .map_err(anyhow::Error::from),
         Command::GenerateVerifier(args) => match args.protocal.as_str() {
             "groth16" => groth16::api::generate_verifier(&args.vk_file, &args.sol),
             _ => {